@@ -25,7 +25,7 @@ fn cpu_fetch_benchmark(c: &mut Criterion) {
     c.bench_function("cpu_fetch", |b| {
         b.iter(|| {
             cpu.pc %= 4096;
-            cpu.fetch(memory);
+            cpu.fetch(&memory).unwrap();
         })
     });
 }
@@ -35,7 +35,7 @@ fn cpu_fmt_display_benchmark(c: &mut Criterion) {
 
     c.bench_function("cpu_fmt_display", |b| {
         b.iter(|| {
-            format!("{cpu}");
+            let _ = format!("{cpu}");
         })
     });
 }
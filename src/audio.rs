@@ -0,0 +1,405 @@
+use crate::{
+    constant::{
+        audio::{DEFAULT_TONE_FREQUENCY, DEFAULT_VOLUME, WAV_SAMPLE_RATE},
+        chip8::FRAMES_PER_SECOND,
+    },
+    display::SharedBackend,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+};
+
+pub trait AudioBackend {
+    // Called once per emulated frame with whether the buzzer sounds during it
+    fn frame(&mut self, playing: bool);
+
+    // Called when the machine stops advancing frames, e.g. while paused
+    fn pause(&mut self) {}
+}
+
+impl<B: AudioBackend> AudioBackend for SharedBackend<B> {
+    fn frame(&mut self, playing: bool) {
+        self.borrow_mut().frame(playing);
+    }
+
+    fn pause(&mut self) {
+        self.borrow_mut().pause();
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SquareWave {
+    // In Hz
+    pub frequency: f32,
+    // From 0.0 to 1.0
+    pub volume: f32,
+    phase: f32,
+}
+
+impl Default for SquareWave {
+    fn default() -> Self {
+        return Self::new(DEFAULT_TONE_FREQUENCY, DEFAULT_VOLUME);
+    }
+}
+
+impl SquareWave {
+    pub fn new(frequency: f32, volume: f32) -> Self {
+        return SquareWave {
+            frequency,
+            volume: volume.clamp(0.0, 1.0),
+            phase: 0.0,
+        };
+    }
+
+    pub fn next_sample(&mut self, sample_rate: u32) -> f32 {
+        let sample = if self.phase < 0.5 {
+            self.volume
+        } else {
+            -self.volume
+        };
+        self.phase = (self.phase + self.frequency / sample_rate as f32).fract();
+
+        return sample;
+    }
+}
+
+// Records when the buzzer sounds, counted in frames, without playing anything
+#[derive(Default)]
+pub struct NullAudio {
+    frames: usize,
+    intervals: Vec<Range<usize>>,
+}
+
+impl NullAudio {
+    pub fn new() -> Self {
+        return NullAudio {
+            frames: 0,
+            intervals: Vec::new(),
+        };
+    }
+
+    pub fn frames(&self) -> usize {
+        return self.frames;
+    }
+
+    pub fn intervals(&self) -> &[Range<usize>] {
+        return &self.intervals;
+    }
+}
+
+impl AudioBackend for NullAudio {
+    fn frame(&mut self, playing: bool) {
+        if playing {
+            match self.intervals.last_mut() {
+                Some(interval) if interval.end == self.frames => interval.end += 1,
+                _ => self.intervals.push(self.frames..self.frames + 1),
+            }
+        }
+
+        self.frames += 1;
+    }
+}
+
+// Writes the buzzer as 16-bit mono PCM. The header is completed by finish,
+// which also runs on drop.
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    wave: SquareWave,
+    sample_rate: u32,
+    samples: u32,
+    sample_budget: u32,
+    error: Option<io::Error>,
+}
+
+impl WavWriter<BufWriter<File>> {
+    pub fn create(path: &Path, wave: SquareWave) -> io::Result<Self> {
+        return Self::new(BufWriter::new(File::create(path)?), wave, WAV_SAMPLE_RATE);
+    }
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(writer: W, wave: SquareWave, sample_rate: u32) -> io::Result<Self> {
+        let mut wav = WavWriter {
+            writer,
+            wave,
+            sample_rate,
+            samples: 0,
+            sample_budget: 0,
+            error: None,
+        };
+        wav.write_header()?;
+
+        return Ok(wav);
+    }
+
+    pub fn samples(&self) -> u32 {
+        return self.samples;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        return &self.writer;
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let data_size = self.samples * 2;
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(b"RIFF")?;
+        self.writer.write_all(&(36 + data_size).to_le_bytes())?;
+        self.writer.write_all(b"WAVEfmt ")?;
+        self.writer.write_all(&16u32.to_le_bytes())?;
+        // PCM, mono
+        self.writer.write_all(&1u16.to_le_bytes())?;
+        self.writer.write_all(&1u16.to_le_bytes())?;
+        self.writer.write_all(&self.sample_rate.to_le_bytes())?;
+        self.writer
+            .write_all(&(self.sample_rate * 2).to_le_bytes())?;
+        self.writer.write_all(&2u16.to_le_bytes())?;
+        self.writer.write_all(&16u16.to_le_bytes())?;
+        self.writer.write_all(b"data")?;
+        self.writer.write_all(&data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+
+        return Ok(());
+    }
+
+    fn write_frame(&mut self, playing: bool) -> io::Result<()> {
+        self.sample_budget += self.sample_rate;
+        let samples = self.sample_budget / FRAMES_PER_SECOND as u32;
+        self.sample_budget %= FRAMES_PER_SECOND as u32;
+
+        for _ in 0..samples {
+            let sample = if playing {
+                (self.wave.next_sample(self.sample_rate) * i16::MAX as f32) as i16
+            } else {
+                0
+            };
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.samples += samples;
+
+        return Ok(());
+    }
+
+    // Returns the first error hit while writing, if any
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        self.write_header()?;
+        self.writer.flush()?;
+
+        return Ok(());
+    }
+}
+
+impl<W: Write + Seek> AudioBackend for WavWriter<W> {
+    fn frame(&mut self, playing: bool) {
+        if self.error.is_some() {
+            return;
+        }
+
+        if let Err(error) = self.write_frame(playing) {
+            self.error = Some(error);
+        }
+    }
+}
+
+impl<W: Write + Seek> Drop for WavWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+#[cfg(feature = "audio")]
+pub use device::{DeviceAudio, DeviceAudioError};
+
+#[cfg(feature = "audio")]
+mod device {
+    use super::{AudioBackend, SquareWave};
+    use core::fmt;
+    use cpal::{
+        FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+        traits::{DeviceTrait, HostTrait, StreamTrait},
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    pub enum DeviceAudioError {
+        NoDevice,
+        Device(String),
+    }
+
+    impl fmt::Display for DeviceAudioError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                DeviceAudioError::NoDevice => write!(f, "no sound output device found"),
+                DeviceAudioError::Device(message) => write!(f, "{message}"),
+            }
+        }
+    }
+
+    impl std::error::Error for DeviceAudioError {}
+
+    struct Beeper {
+        playing: bool,
+        wave: SquareWave,
+    }
+
+    // Plays the buzzer on the default output device
+    pub struct DeviceAudio {
+        beeper: Arc<Mutex<Beeper>>,
+        _stream: Stream,
+    }
+
+    impl DeviceAudio {
+        pub fn try_new(wave: SquareWave) -> Result<Self, DeviceAudioError> {
+            let device = cpal::default_host()
+                .default_output_device()
+                .ok_or(DeviceAudioError::NoDevice)?;
+            let supported_config = device
+                .default_output_config()
+                .map_err(|error| DeviceAudioError::Device(error.to_string()))?;
+
+            let beeper = Arc::new(Mutex::new(Beeper {
+                playing: false,
+                wave,
+            }));
+            let config = supported_config.config();
+
+            let stream = match supported_config.sample_format() {
+                SampleFormat::I16 => Self::build_stream::<i16>(&device, &config, &beeper),
+                SampleFormat::U16 => Self::build_stream::<u16>(&device, &config, &beeper),
+                SampleFormat::I32 => Self::build_stream::<i32>(&device, &config, &beeper),
+                SampleFormat::F32 => Self::build_stream::<f32>(&device, &config, &beeper),
+                format => {
+                    return Err(DeviceAudioError::Device(format!(
+                        "unsupported sample format {format}"
+                    )));
+                }
+            }?;
+            stream
+                .play()
+                .map_err(|error| DeviceAudioError::Device(error.to_string()))?;
+
+            return Ok(DeviceAudio {
+                beeper,
+                _stream: stream,
+            });
+        }
+
+        fn build_stream<T: SizedSample + FromSample<f32>>(
+            device: &cpal::Device,
+            config: &StreamConfig,
+            beeper: &Arc<Mutex<Beeper>>,
+        ) -> Result<Stream, DeviceAudioError> {
+            let beeper = beeper.clone();
+            let channels = config.channels as usize;
+            let sample_rate = config.sample_rate;
+
+            return device
+                .build_output_stream(
+                    config,
+                    move |data: &mut [T], _| {
+                        let mut beeper = beeper.lock().unwrap();
+                        for frame in data.chunks_mut(channels) {
+                            let sample = if beeper.playing {
+                                beeper.wave.next_sample(sample_rate)
+                            } else {
+                                0.0
+                            };
+                            frame.fill(T::from_sample(sample));
+                        }
+                    },
+                    |error| eprintln!("audio error: {error}"),
+                    None,
+                )
+                .map_err(|error| DeviceAudioError::Device(error.to_string()));
+        }
+
+        pub fn set_wave(&mut self, wave: SquareWave) {
+            self.beeper.lock().unwrap().wave = wave;
+        }
+    }
+
+    impl AudioBackend for DeviceAudio {
+        fn frame(&mut self, playing: bool) {
+            self.beeper.lock().unwrap().playing = playing;
+        }
+
+        fn pause(&mut self) {
+            self.frame(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip8::CHIP8, display::HeadlessBackend};
+    use std::{cell::RefCell, io::Cursor};
+
+    #[test]
+    fn sound_timer_drives_audio() {
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        // LD V0, 3; LD ST, V0; JP 0x204
+        chip8
+            .load_rom(&[0x60, 0x03, 0xF0, 0x18, 0x12, 0x04])
+            .unwrap();
+        let audio = SharedBackend::new(RefCell::new(NullAudio::new()));
+        chip8.set_audio_backend(Box::new(audio.clone()));
+
+        for _ in 0..6 {
+            chip8.run_frame();
+        }
+        chip8.control().pause();
+        chip8.run_frame();
+
+        assert_eq!(audio.borrow().frames(), 6);
+        assert_eq!(audio.borrow().intervals(), &[Range { start: 1, end: 4 }]);
+    }
+
+    #[test]
+    fn null_audio_intervals() {
+        let mut audio = NullAudio::new();
+        for playing in [false, true, true, false, true, false] {
+            audio.frame(playing);
+        }
+
+        assert_eq!(audio.frames(), 6);
+        assert_eq!(audio.intervals(), &[1..3, 4..5]);
+    }
+
+    #[test]
+    fn wav_writer() {
+        let mut wav =
+            WavWriter::new(Cursor::new(Vec::new()), SquareWave::new(1000.0, 0.5), 8000).unwrap();
+        wav.frame(true);
+        wav.frame(false);
+        wav.frame(true);
+        wav.finish().unwrap();
+
+        // 8000 / 60 samples per frame with the remainder carried over
+        assert_eq!(wav.samples(), 133 + 133 + 134);
+
+        let data = wav.get_ref().get_ref();
+        assert_eq!(&data[0..4], b"RIFF");
+        assert_eq!(&data[36..40], b"data");
+        assert_eq!(
+            u32::from_le_bytes(data[40..44].try_into().unwrap()),
+            wav.samples() * 2
+        );
+        assert_eq!(data.len(), 44 + wav.samples() as usize * 2);
+
+        let sample = |i: usize| i16::from_le_bytes([data[44 + i * 2], data[45 + i * 2]]);
+        // 8 samples per period, high for the first half
+        assert_eq!(sample(0), i16::MAX / 2);
+        assert_eq!(sample(4), -(i16::MAX / 2));
+        assert_eq!(sample(133), 0);
+        assert_eq!(sample(265), 0);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::constant::browser::{RECENT_ROMS_LIMIT, ROM_EXTENSIONS};
+use std::{
+    env, fs, io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MenuKey {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MenuAction {
+    None,
+    Close,
+    Launch(PathBuf),
+}
+
+pub fn is_rom(path: &Path) -> bool {
+    return path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ROM_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        });
+}
+
+// ROM files directly inside the directory, sorted by name
+pub fn list_roms(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut roms = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_file() && is_rom(&path) {
+            roms.push(path);
+        }
+    }
+    roms.sort_by_key(|path| path.file_name().map(|name| name.to_ascii_lowercase()));
+
+    return Ok(roms);
+}
+
+// Most recently played ROMs first, kept in a text file with one path per line
+pub struct RecentRoms {
+    file: Option<PathBuf>,
+    roms: Vec<PathBuf>,
+}
+
+impl Default for RecentRoms {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecentRoms {
+    // Only kept in memory
+    pub fn new() -> Self {
+        return RecentRoms {
+            file: None,
+            roms: Vec::new(),
+        };
+    }
+
+    // A missing file is an empty list
+    pub fn load(file: PathBuf) -> io::Result<Self> {
+        let roms = match fs::read_to_string(&file) {
+            Ok(text) => text.lines().map(PathBuf::from).collect(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+
+        return Ok(RecentRoms {
+            file: Some(file),
+            roms,
+        });
+    }
+
+    // $XDG_STATE_HOME/chip-8/recent-roms, falling back to ~/.local/state
+    pub fn default_file() -> Option<PathBuf> {
+        let state = match env::var_os("XDG_STATE_HOME") {
+            Some(state) if !state.is_empty() => PathBuf::from(state),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+        };
+
+        return Some(state.join("chip-8").join("recent-roms"));
+    }
+
+    pub fn roms(&self) -> &[PathBuf] {
+        return &self.roms;
+    }
+
+    // Moves the ROM to the front and saves the list if it has a file
+    pub fn add(&mut self, rom: &Path) -> io::Result<()> {
+        let rom = fs::canonicalize(rom).unwrap_or_else(|_| rom.to_path_buf());
+        self.roms.retain(|recent| *recent != rom);
+        self.roms.insert(0, rom);
+        self.roms.truncate(RECENT_ROMS_LIMIT);
+
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(directory) = file.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let text: String = self
+            .roms
+            .iter()
+            .map(|rom| format!("{}\n", rom.display()))
+            .collect();
+
+        return fs::write(file, text);
+    }
+}
+
+// Recently played ROMs that still exist followed by the ROMs of a directory
+pub struct RomBrowser {
+    directory: PathBuf,
+    entries: Vec<PathBuf>,
+    recent: usize,
+    selected: usize,
+}
+
+impl RomBrowser {
+    pub fn open(directory: &Path, recent_roms: &RecentRoms) -> io::Result<Self> {
+        let mut entries: Vec<PathBuf> = recent_roms
+            .roms()
+            .iter()
+            .filter(|rom| rom.is_file())
+            .cloned()
+            .collect();
+        let recent = entries.len();
+        entries.extend(list_roms(directory)?);
+
+        return Ok(RomBrowser {
+            directory: directory.to_path_buf(),
+            entries,
+            recent,
+            selected: 0,
+        });
+    }
+
+    pub fn title(&self) -> String {
+        return format!("ROMs in {}", self.directory.display());
+    }
+
+    // Recent ROMs are marked with a star
+    pub fn lines(&self) -> Vec<String> {
+        return self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, rom)| {
+                let name = rom.file_name().unwrap_or_default().to_string_lossy();
+                match i < self.recent {
+                    true => format!("* {name}"),
+                    false => format!("  {name}"),
+                }
+            })
+            .collect();
+    }
+
+    pub fn selected(&self) -> usize {
+        return self.selected;
+    }
+
+    pub fn handle(&mut self, key: MenuKey) -> MenuAction {
+        match key {
+            MenuKey::Up => self.selected = self.selected.saturating_sub(1),
+            MenuKey::Down => {
+                self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1))
+            }
+            MenuKey::Select => {
+                if let Some(rom) = self.entries.get(self.selected) {
+                    return MenuAction::Launch(rom.clone());
+                }
+            }
+            MenuKey::Back => return MenuAction::Close,
+        }
+
+        return MenuAction::None;
+    }
+}
+
+// Lines to show so that the selected one stays on a screen of the given
+// number of rows
+pub fn visible_range(lines: usize, selected: usize, rows: usize) -> Range<usize> {
+    let rows = rows.max(1);
+    let start = selected
+        .saturating_sub(rows / 2)
+        .min(lines.saturating_sub(rows));
+
+    return start..(start + rows).min(lines);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip8::CHIP8, display::HeadlessBackend, screenshot::ImageFormat};
+
+    #[test]
+    fn browse_directory() {
+        let directory = env::temp_dir().join(format!("chip8-browser-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for name in ["b.sc8", "A.CH8", "c.xo8", "notes.txt"] {
+            fs::write(directory.join(name), [0x00, 0xE0]).unwrap();
+        }
+
+        let mut recent = RecentRoms::load(directory.join("state/recent-roms")).unwrap();
+        assert!(recent.roms().is_empty());
+        recent.add(&directory.join("c.xo8")).unwrap();
+        recent.add(&directory.join("A.CH8")).unwrap();
+        recent.add(&directory.join("c.xo8")).unwrap();
+        recent.add(&directory.join("deleted.ch8")).unwrap();
+        assert_eq!(
+            RecentRoms::load(directory.join("state/recent-roms"))
+                .unwrap()
+                .roms(),
+            recent.roms()
+        );
+
+        let mut browser = RomBrowser::open(&directory, &recent).unwrap();
+        assert_eq!(
+            browser.lines(),
+            ["* c.xo8", "* A.CH8", "  A.CH8", "  b.sc8", "  c.xo8"]
+        );
+
+        assert_eq!(browser.handle(MenuKey::Up), MenuAction::None);
+        for _ in 0..10 {
+            browser.handle(MenuKey::Down);
+        }
+        assert_eq!(browser.selected(), 4);
+        assert!(matches!(
+            browser.handle(MenuKey::Select),
+            MenuAction::Launch(rom) if rom.ends_with("c.xo8")
+        ));
+        assert_eq!(browser.handle(MenuKey::Back), MenuAction::Close);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn scrolling() {
+        assert_eq!(visible_range(3, 2, 10), 0..3);
+        assert_eq!(visible_range(20, 0, 5), 0..5);
+        assert_eq!(visible_range(20, 10, 5), 8..13);
+        assert_eq!(visible_range(20, 19, 5), 15..20);
+    }
+
+    #[test]
+    fn load_rom_file_resets() {
+        let directory = env::temp_dir().join(format!("chip8-reset-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        // HIGH; LD V5, 0x11; LD F, V5; DRW V5, V5, 5
+        fs::write(
+            directory.join("draw.sc8"),
+            [0x00, 0xFF, 0x65, 0x11, 0xF5, 0x29, 0xD5, 0x55],
+        )
+        .unwrap();
+        fs::write(directory.join("empty.ch8"), []).unwrap();
+
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        chip8.load_rom_file(&directory.join("draw.sc8")).unwrap();
+        chip8.run_cycles(4);
+        let drawn = chip8.screenshot(ImageFormat::Pbm, 1).unwrap();
+        assert!(drawn.starts_with(b"P4\n128 64\n"));
+
+        chip8.load_rom_file(&directory.join("empty.ch8")).unwrap();
+        let info = chip8.debug_info();
+        assert_eq!(info.pc, 0x200);
+        assert_eq!(info.registers, [0; 16]);
+        let blank = chip8.screenshot(ImageFormat::Pbm, 1).unwrap();
+        assert_eq!(blank, [b"P4\n64 32\n".as_slice(), &[0; 8 * 32]].concat());
+
+        assert!(chip8.load_rom_file(&directory.join("missing.ch8")).is_err());
+        // Nothing could be picked from a browser the backend does not show
+        assert!(chip8.open_browser().is_err());
+        assert!(!chip8.is_browsing());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
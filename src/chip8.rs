@@ -1,80 +1,887 @@
 use crate::{
-    constant::{chip8::CPU_INSTRUCTION_PER_SECOND, ram::ROM_START_LOCATION},
-    cpu::CPU,
-    display::{CLIBackend, Display, DisplayBackend},
+    audio::AudioBackend,
+    browser::{MenuAction, RecentRoms, RomBrowser},
+    constant::{
+        chip8::{CPU_INSTRUCTION_PER_SECOND, FRAMES_PER_SECOND},
+        debugger::DEBUGGER_PROMPT,
+        ram::ROM_START_LOCATION,
+    },
+    cpu::{self, CPU, ExecutionError},
+    debugger::{Debugger, DebuggerAction},
+    display::{CLIBackend, Display, DisplayBackend, DisplayState, SharedBackend},
+    hud::DebugInfo,
+    input::{Hotkey, InputBackend},
+    quirks::{Platform, Quirks},
     ram::{Ram, RomError},
+    recording::{GifRecorder, RecordingError},
+    rewind::RewindBuffer,
+    screenshot::{self, ImageFormat, ScreenshotError},
+    state::{StateError, StateReader, StateWriter},
 };
-use core::time;
-use std::{thread::sleep, time::Instant};
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExecutionStatus {
+    Running,
+    WaitingForKey,
+    WaitingForVblank,
+    Paused,
+    Stopped,
+    Exited,
+    Error(ExecutionError),
+}
+
+// Frames and instructions counted over roughly the last second
+struct Throughput {
+    since: Instant,
+    frames: u32,
+    instructions: u64,
+    frames_per_second: f32,
+    instructions_per_second: f32,
+}
+
+impl Throughput {
+    fn new() -> Self {
+        return Throughput {
+            since: Instant::now(),
+            frames: 0,
+            instructions: 0,
+            frames_per_second: 0.0,
+            instructions_per_second: 0.0,
+        };
+    }
+
+    fn frame(&mut self, instructions: u64) {
+        self.frames += 1;
+        self.instructions += instructions;
+
+        let elapsed = self.since.elapsed().as_secs_f32();
+        if elapsed >= 1.0 {
+            *self = Throughput {
+                frames_per_second: self.frames as f32 / elapsed,
+                instructions_per_second: self.instructions as f32 / elapsed,
+                ..Throughput::new()
+            };
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RunControl {
+    paused: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+}
 
-pub struct CHIP8<B: DisplayBackend> {
+impl RunControl {
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.paused.load(Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        return self.stopped.load(Ordering::Relaxed);
+    }
+
+    fn status(&self) -> ExecutionStatus {
+        if self.is_stopped() {
+            return ExecutionStatus::Stopped;
+        }
+        if self.is_paused() {
+            return ExecutionStatus::Paused;
+        }
+
+        return ExecutionStatus::Running;
+    }
+}
+
+pub struct CHIP8<D: DisplayBackend, I: InputBackend> {
     cpu: CPU,
     ram: Ram,
-    display: Display<B>,
+    display: Display<D>,
+    input: I,
+    audio: Option<Box<dyn AudioBackend>>,
+    control: RunControl,
+    fault: Option<ExecutionError>,
+    debugger: Option<Debugger>,
+    instructions_per_second: usize,
+    cycle_budget: usize,
+    state_path: Option<PathBuf>,
+    screenshot_path: Option<PathBuf>,
+    screenshot_scale: usize,
+    recording_path: Option<PathBuf>,
+    recording_scale: usize,
+    recorder: Option<GifRecorder<BufWriter<File>>>,
+    rom_loaded: bool,
+    rom_directory: Option<PathBuf>,
+    recent_roms: RecentRoms,
+    browser: Option<RomBrowser>,
+    // Whether the browser is on screen as it currently is
+    browser_shown: bool,
+    rewind: RewindBuffer,
+    instructions_executed: u64,
+    throughput: Throughput,
 }
 
-impl Default for CHIP8<CLIBackend> {
+impl Default for CHIP8<SharedBackend<CLIBackend>, SharedBackend<CLIBackend>> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl CHIP8<CLIBackend> {
+impl CHIP8<SharedBackend<CLIBackend>, SharedBackend<CLIBackend>> {
     pub fn new() -> Self {
-        return CHIP8 {
-            cpu: CPU::new(),
-            ram: Ram::new(),
-            display: Display::<CLIBackend>::new(CLIBackend::default()),
-        };
+        return Self::new_custom_display_backend(CLIBackend::default());
+    }
+}
+
+impl<B: DisplayBackend + InputBackend> CHIP8<SharedBackend<B>, SharedBackend<B>> {
+    // Uses one backend for both the display and the input
+    pub fn new_custom_display_backend(backend: B) -> Self {
+        let backend = SharedBackend::new(RefCell::new(backend));
+
+        return Self::new_custom_backends(backend.clone(), backend);
     }
 }
 
-impl<B: DisplayBackend> CHIP8<B> {
-    pub fn new_custom_display_backend(display_backend: B) -> Self {
+impl<D: DisplayBackend, I: InputBackend> CHIP8<D, I> {
+    pub fn new_custom_backends(display_backend: D, input_backend: I) -> Self {
         return CHIP8 {
             cpu: CPU::new(),
             ram: Ram::new(),
-            display: Display::<B>::new(display_backend),
+            display: Display::<D>::new(display_backend),
+            input: input_backend,
+            audio: None,
+            control: RunControl::default(),
+            fault: None,
+            debugger: None,
+            instructions_per_second: CPU_INSTRUCTION_PER_SECOND,
+            cycle_budget: 0,
+            state_path: None,
+            screenshot_path: None,
+            screenshot_scale: 1,
+            recording_path: None,
+            recording_scale: 1,
+            recorder: None,
+            rom_loaded: false,
+            rom_directory: None,
+            recent_roms: RecentRoms::new(),
+            browser: None,
+            browser_shown: false,
+            rewind: RewindBuffer::new(),
+            instructions_executed: 0,
+            throughput: Throughput::new(),
         };
     }
 
+    pub fn set_instructions_per_second(&mut self, instructions_per_second: usize) {
+        self.instructions_per_second = instructions_per_second.max(1);
+        self.cycle_budget = 0;
+    }
+
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: usize) {
+        self.set_instructions_per_second(instructions_per_frame * FRAMES_PER_SECOND);
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        return self.cpu.quirks();
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.cpu.set_quirks(quirks);
+    }
+
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.cpu.set_quirks(platform.quirks());
+        self.ram.resize(platform.memory_size());
+    }
+
+    pub fn set_present_on_draw(&mut self, present_on_draw: bool) {
+        self.display.present_on_draw = present_on_draw;
+    }
+
+    // Debugging starts stopped before the next instruction, keeping the
+    // breakpoints of a debugger that is already attached
+    pub fn set_debug(&mut self, debug: bool) {
+        match debug {
+            true => {
+                self.debugger.get_or_insert_with(Debugger::new);
+            }
+            false => self.debugger = None,
+        }
+    }
+
+    pub fn debugger(&self) -> Option<&Debugger> {
+        return self.debugger.as_ref();
+    }
+
+    pub fn debugger_mut(&mut self) -> Option<&mut Debugger> {
+        return self.debugger.as_mut();
+    }
+
+    pub fn display_backend(&self) -> &D {
+        return &self.display.backend;
+    }
+
+    pub fn display_backend_mut(&mut self) -> &mut D {
+        return &mut self.display.backend;
+    }
+
+    pub fn input_backend(&self) -> &I {
+        return &self.input;
+    }
+
+    pub fn input_backend_mut(&mut self) -> &mut I {
+        return &mut self.input;
+    }
+
+    pub fn set_audio_backend(&mut self, audio_backend: Box<dyn AudioBackend>) {
+        self.audio = Some(audio_backend);
+    }
+
+    pub fn control(&self) -> RunControl {
+        return self.control.clone();
+    }
+
     pub fn load_rom(&mut self, rom_data: &[u8]) -> Result<(), RomError> {
+        self.rewind.clear();
         self.cpu.pc = ROM_START_LOCATION as u16;
+        self.ram.load_rom(rom_data)?;
+        self.rom_loaded = true;
 
-        return self.ram.load_rom(rom_data);
+        return Ok(());
     }
 
-    fn tick(&mut self) {
-        let instruction = self.cpu.fetch(self.ram.memory);
-        let instruction = CPU::decode(instruction);
+    // Puts the machine back into its power-on state, keeping the platform
+    // and the emulator settings
+    pub fn reset(&mut self) {
+        self.cpu = CPU::with_quirks(self.cpu.quirks());
+        self.ram = Ram::with_size(self.ram.memory.len());
+        self.display.reset();
+        self.fault = None;
+        self.cycle_budget = 0;
+        self.rewind.clear();
+        self.rom_loaded = false;
+    }
+
+    // Resets the machine before loading. Save states, screenshots and
+    // recordings made with the hotkeys go next to the ROM file.
+    pub fn load_rom_file(&mut self, path: &Path) -> Result<(), RomError> {
+        let rom_data = std::fs::read(path)?;
+        self.reset();
+        self.load_rom(&rom_data)?;
+
+        self.state_path = Some(path.with_extension("state"));
+        self.screenshot_path = Some(path.with_extension("png"));
+        self.recording_path = Some(path.with_extension("gif"));
+
+        if let Err(error) = self.recent_roms.add(path) {
+            self.display
+                .log(format!("Could not save the recent ROMs: {error}"));
+        }
+
+        return Ok(());
+    }
+
+    // Directory listed by the ROM browser
+    pub fn set_rom_directory(&mut self, directory: PathBuf) {
+        self.rom_directory = Some(directory);
+    }
+
+    // ROM files loaded with load_rom_file are added to this list
+    pub fn set_recent_roms(&mut self, recent_roms: RecentRoms) {
+        self.recent_roms = recent_roms;
+    }
+
+    // The machine is paused while the browser is open
+    pub fn open_browser(&mut self) -> io::Result<()> {
+        if !self.display.backend.shows_menus() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the display backend cannot show the ROM browser",
+            ));
+        }
+
+        let directory = self.rom_directory.as_deref().unwrap_or(Path::new("."));
+        self.browser = Some(RomBrowser::open(directory, &self.recent_roms)?);
+        self.browser_shown = false;
+
+        if let Some(audio) = &mut self.audio {
+            audio.pause();
+        }
+
+        return Ok(());
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        return self.browser.is_some();
+    }
+
+    // Handles one frame of the ROM browser. Returns false when it was closed
+    // before any ROM was loaded.
+    fn browse(&mut self) -> bool {
+        let Some(browser) = &mut self.browser else {
+            return true;
+        };
+
+        let keys = self.input.menu_keys();
+        let action = keys
+            .iter()
+            .map(|&key| browser.handle(key))
+            .find(|action| *action != MenuAction::None)
+            .unwrap_or(MenuAction::None);
+
+        match action {
+            MenuAction::None => {
+                if !keys.is_empty() || !self.browser_shown {
+                    self.display.backend.render_menu(
+                        &browser.title(),
+                        &browser.lines(),
+                        browser.selected(),
+                    );
+                    self.browser_shown = true;
+                }
+            }
+            MenuAction::Close => {
+                self.browser = None;
+                if !self.rom_loaded {
+                    return false;
+                }
+                self.display.mark_dirty();
+                self.display.present();
+            }
+            // A ROM that fails to load leaves the browser open
+            MenuAction::Launch(path) => match self.load_rom_file(&path) {
+                Ok(()) => {
+                    self.browser = None;
+                    self.display.present();
+                    self.display.log(format!("Loaded '{}'", path.display()));
+                }
+                Err(error) => {
+                    self.display
+                        .log(format!("Could not load '{}': {error}", path.display()));
+                }
+            },
+        }
+
+        return true;
+    }
+
+    pub fn fault(&self) -> Option<ExecutionError> {
+        return self.fault;
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        self.cpu.save_state(&mut writer);
+        self.ram.save_state(&mut writer);
+        writer.write_u32(self.cycle_budget as u32);
+        self.display.save_state(&mut writer);
+
+        return writer.finish();
+    }
+
+    // The machine is left untouched if the state cannot be read
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(data)?;
+        let cpu = CPU::read_state(&mut reader)?;
+        let ram = Ram::read_state(&mut reader)?;
+        let cycle_budget = reader.read_u32()? as usize;
+        let display = DisplayState::read_state(&mut reader)?;
+        reader.finish()?;
+
+        self.cpu = cpu;
+        self.display.load_state(display);
+        self.ram = ram;
+        self.cycle_budget = cycle_budget % FRAMES_PER_SECOND;
+        self.fault = None;
+
+        return Ok(());
+    }
+
+    pub fn save_state_to_file(&self, path: &Path) -> Result<(), StateError> {
+        std::fs::write(path, self.save_state())?;
+
+        return Ok(());
+    }
+
+    pub fn load_state_from_file(&mut self, path: &Path) -> Result<(), StateError> {
+        let data = std::fs::read(path)?;
+
+        return self.load_state(&data);
+    }
 
-        self.cpu
-            .execute(instruction, &mut self.ram.memory, &mut self.display);
+    // File used by the save and load state hotkeys
+    pub fn set_state_path(&mut self, path: PathBuf) {
+        self.state_path = Some(path);
     }
 
-    fn debug_tick(&mut self) {
-        let instruction = self.cpu.fetch(self.ram.memory);
+    // Encodes the framebuffer with the display backend's palette
+    pub fn screenshot(
+        &self,
+        format: ImageFormat,
+        scale: usize,
+    ) -> Result<Vec<u8>, ScreenshotError> {
+        return screenshot::encode(
+            &self.display.pixels,
+            self.display.width(),
+            self.display.height(),
+            scale,
+            &self.display.backend.palette().unwrap_or_default(),
+            format,
+        );
+    }
+
+    // The format is picked from the file extension
+    pub fn save_screenshot(&self, path: &Path, scale: usize) -> Result<(), ScreenshotError> {
+        let format = ImageFormat::from_path(path)
+            .ok_or_else(|| ScreenshotError::UnsupportedFormat(path.display().to_string()))?;
+        std::fs::write(path, self.screenshot(format, scale)?)?;
+
+        return Ok(());
+    }
+
+    // The screenshot hotkey numbers its files after this path
+    pub fn set_screenshot_path(&mut self, path: PathBuf) {
+        self.screenshot_path = Some(path);
+    }
+
+    // Size of one CHIP-8 pixel in screenshots taken with the hotkey
+    pub fn set_screenshot_scale(&mut self, scale: usize) {
+        self.screenshot_scale = scale.max(1);
+    }
+
+    // Captures the display once per frame until stop_recording is called.
+    // The GIF keeps the resolution the display had when recording started.
+    pub fn start_recording(&mut self, path: &Path, scale: usize) -> Result<(), RecordingError> {
+        let scale = scale.max(1);
+        self.recorder = Some(GifRecorder::create(
+            path,
+            self.display.width() * scale,
+            self.display.height() * scale,
+            &self.display.backend.palette().unwrap_or_default(),
+        )?);
+
+        return Ok(());
+    }
+
+    // Returns the number of frames recorded, or None when not recording
+    pub fn stop_recording(&mut self) -> Result<Option<u64>, RecordingError> {
+        let Some(recorder) = self.recorder.take() else {
+            return Ok(None);
+        };
+        let ticks = recorder.ticks();
+        recorder
+            .finish()?
+            .into_inner()
+            .map_err(|error| error.into_error())?;
+
+        return Ok(Some(ticks));
+    }
+
+    pub fn is_recording(&self) -> bool {
+        return self.recorder.is_some();
+    }
+
+    // The recording hotkey numbers its files after this path
+    pub fn set_recording_path(&mut self, path: PathBuf) {
+        self.recording_path = Some(path);
+    }
+
+    // Size of one CHIP-8 pixel in recordings started with the hotkey
+    pub fn set_recording_scale(&mut self, scale: usize) {
+        self.recording_scale = scale.max(1);
+    }
+
+    fn record_frame(&mut self) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+
+        if let Err(error) = recorder.capture(
+            &self.display.pixels,
+            self.display.width(),
+            self.display.height(),
+        ) {
+            self.recorder = None;
+            self.display.log(format!("Recording stopped: {error}"));
+        }
+    }
+
+    pub fn debug_info(&self) -> DebugInfo {
+        return DebugInfo {
+            pc: self.cpu.pc,
+            index: self.cpu.index(),
+            registers: *self.cpu.registers(),
+            stack_depth: self.cpu.stack_depth(),
+            delay_timer: self.cpu.delay_timer(),
+            sound_timer: self.cpu.sound_timer(),
+            instruction: cpu::disassemble_at(&self.ram.memory, self.cpu.pc as usize),
+            frames_per_second: self.throughput.frames_per_second,
+            instructions_per_second: self.throughput.instructions_per_second,
+        };
+    }
+
+    // A frame snapshot is taken at the start of every frame. Passing 0
+    // frames disables rewinding.
+    pub fn set_rewind_limits(&mut self, frames: usize, memory_limit: usize) {
+        self.rewind.set_limits(frames, memory_limit);
+    }
+
+    pub fn rewind_frames(&self) -> usize {
+        return self.rewind.len();
+    }
+
+    // Rolls the machine back to the start of the given number of frames ago
+    // and returns how many frames were actually rewound
+    pub fn rewind(&mut self, frames: usize) -> usize {
+        let available = self.rewind.len().min(frames);
+
+        if let Some(snapshot) = self.rewind.rewind(frames) {
+            self.load_state(&snapshot)
+                .expect("rewind snapshots are always valid");
+        }
+
+        return available;
+    }
+
+    // Returns true when the frame was spent rewinding
+    fn handle_hotkeys(&mut self) -> bool {
+        let mut rewound = false;
+
+        for hotkey in self.input.hotkeys() {
+            let path = match hotkey {
+                Hotkey::SaveState | Hotkey::LoadState => self.state_path.clone(),
+                Hotkey::Screenshot => self.screenshot_path.as_deref().map(next_free_path),
+                Hotkey::Record => self.recording_path.as_deref().map(next_free_path),
+                Hotkey::Rewind | Hotkey::Browse | Hotkey::Break => None,
+            };
+
+            let message = match (hotkey, path) {
+                (Hotkey::Rewind, _) => {
+                    self.rewind(1);
+                    rewound = true;
+                    continue;
+                }
+                (Hotkey::Break, _) => {
+                    self.debugger.get_or_insert_with(Debugger::new).interrupt();
+                    continue;
+                }
+                (Hotkey::Browse, _) => match self.open_browser() {
+                    Ok(()) => continue,
+                    Err(error) => format!("Could not open the ROM browser: {error}"),
+                },
+                (Hotkey::Record, _) if self.is_recording() => match self.stop_recording() {
+                    Ok(frames) => format!("Recorded {} frames", frames.unwrap_or_default()),
+                    Err(error) => format!("Could not finish recording: {error}"),
+                },
+                (_, None) => continue,
+                (Hotkey::SaveState, Some(path)) => match self.save_state_to_file(&path) {
+                    Ok(()) => format!("Saved state to '{}'", path.display()),
+                    Err(error) => format!("Could not save state: {error}"),
+                },
+                (Hotkey::LoadState, Some(path)) => match self.load_state_from_file(&path) {
+                    Ok(()) => format!("Loaded state from '{}'", path.display()),
+                    Err(error) => format!("Could not load state: {error}"),
+                },
+                (Hotkey::Screenshot, Some(path)) => {
+                    match self.save_screenshot(&path, self.screenshot_scale) {
+                        Ok(()) => format!("Saved screenshot to '{}'", path.display()),
+                        Err(error) => format!("Could not save screenshot: {error}"),
+                    }
+                }
+                (Hotkey::Record, Some(path)) => {
+                    match self.start_recording(&path, self.recording_scale) {
+                        Ok(()) => format!("Recording to '{}'", path.display()),
+                        Err(error) => format!("Could not start recording: {error}"),
+                    }
+                }
+            };
+            self.display.log(message);
+        }
+
+        return rewound;
+    }
+
+    pub fn step(&mut self) -> ExecutionStatus {
+        if let Some(error) = self.fault {
+            return ExecutionStatus::Error(error);
+        }
+
+        if self.cpu.has_exited() {
+            return ExecutionStatus::Exited;
+        }
+        if self.cpu.is_waiting_for_vblank() {
+            return ExecutionStatus::WaitingForVblank;
+        }
+
+        if let Err(error) = self.try_step() {
+            // The debugger stops on the faulting instruction, which is
+            // executed again when the machine is resumed from the prompt
+            if let Some(debugger) = &mut self.debugger {
+                debugger.interrupt();
+                self.cpu.pc = error.pc();
+
+                match self.prompt(format!("{}\n{}", error, self.cpu)) {
+                    DebuggerAction::Resume => return ExecutionStatus::Running,
+                    DebuggerAction::Quit => return ExecutionStatus::Stopped,
+                    DebuggerAction::None | DebuggerAction::Output(_) => {}
+                }
+            }
+
+            self.fault = Some(error);
+            return ExecutionStatus::Error(error);
+        }
+
+        // Quitting from the debugger prompt
+        if self.control.is_stopped() {
+            return ExecutionStatus::Stopped;
+        }
+        if self.cpu.has_exited() {
+            return ExecutionStatus::Exited;
+        }
+        if self.cpu.is_waiting_for_key() {
+            return ExecutionStatus::WaitingForKey;
+        }
+        if self.cpu.is_waiting_for_vblank() {
+            return ExecutionStatus::WaitingForVblank;
+        }
+
+        return ExecutionStatus::Running;
+    }
+
+    fn try_step(&mut self) -> Result<(), ExecutionError> {
+        if self.cpu.is_waiting_for_key() {
+            match self.input.read_keys().first() {
+                Some(&key) => self.cpu.press_key(key),
+                None => return Ok(()),
+            }
+        }
+
+        if !self.debug_prompt() {
+            return Ok(());
+        }
+        if let Some(debugger) = &mut self.debugger {
+            debugger.begin_instruction(&self.cpu, &self.ram.memory);
+        }
+
+        let instruction = self.cpu.fetch(&self.ram.memory)?;
         let instruction = CPU::decode(instruction);
 
-        self.cpu
-            .execute(instruction, &mut self.ram.memory, &mut self.display);
+        self.cpu.execute(
+            instruction,
+            &mut self.ram.memory,
+            &mut self.display,
+            &mut self.input,
+        )?;
+        self.instructions_executed += 1;
+
+        if let Some(debugger) = &mut self.debugger {
+            debugger.end_instruction(&self.cpu, &self.ram.memory);
+        }
+
+        return Ok(());
+    }
+
+    // Hands control to the debugger prompt when the debugger stops before the
+    // next instruction. False means the machine was stopped from the prompt.
+    fn debug_prompt(&mut self) -> bool {
+        let Some(debugger) = &mut self.debugger else {
+            return true;
+        };
+        if !debugger.should_stop(&self.cpu) {
+            return true;
+        }
+
+        let message = debugger.stop_message(&self.cpu, &self.ram.memory);
+        return self.prompt(message) != DebuggerAction::Quit;
+    }
+
+    // Reads commands at the debugger prompt until one resumes or stops the
+    // machine. None means no more commands can be read, so the debugger was
+    // detached.
+    fn prompt(&mut self, message: String) -> DebuggerAction {
+        let Some(debugger) = &mut self.debugger else {
+            return DebuggerAction::None;
+        };
+
+        self.display.present();
+        self.display.log(message);
+
+        loop {
+            let Some(line) = self.input.read_command(DEBUGGER_PROMPT) else {
+                debugger.detach();
+                return DebuggerAction::None;
+            };
+
+            match debugger.execute(&line, &mut self.cpu, &mut self.ram.memory) {
+                DebuggerAction::None => {}
+                DebuggerAction::Output(output) => self.display.log(output),
+                DebuggerAction::Resume => return DebuggerAction::Resume,
+                DebuggerAction::Quit => {
+                    self.control.stop();
+                    return DebuggerAction::Quit;
+                }
+            }
+        }
+    }
+
+    pub fn run_cycles(&mut self, cycles: usize) -> ExecutionStatus {
+        let status = self.control.status();
+        if status != ExecutionStatus::Running {
+            return status;
+        }
+
+        for _ in 0..cycles {
+            let status = self.step();
+            if status != ExecutionStatus::Running {
+                return status;
+            }
+        }
+
+        return ExecutionStatus::Running;
+    }
+
+    // Advances the machine by one 60 Hz frame: the delay and sound timers
+    // count down once, the frame's share of instructions is executed and the
+    // display is presented if it changed
+    pub fn run_frame(&mut self) -> ExecutionStatus {
+        let status = self.control.status();
+        if status != ExecutionStatus::Running {
+            if let Some(audio) = &mut self.audio {
+                audio.pause();
+            }
+            return status;
+        }
+
+        if self.rewind.is_enabled() {
+            self.rewind.push(self.save_state());
+        }
+
+        // The buzzer sounds for as many frames as the sound timer was set to
+        if let Some(audio) = &mut self.audio {
+            audio.frame(self.cpu.sound_timer() > 0);
+        }
+
+        self.cpu.vblank();
+        self.cpu.tick_timers();
+
+        self.cycle_budget += self.instructions_per_second;
+        let cycles = self.cycle_budget / FRAMES_PER_SECOND;
+        self.cycle_budget %= FRAMES_PER_SECOND;
+
+        let executed = self.instructions_executed;
+        let status = self.run_cycles(cycles);
+        self.throughput.frame(self.instructions_executed - executed);
 
-        self.display.log(format!("{}\n{}", instruction, self.cpu));
+        if self.display.backend.wants_debug_info() {
+            let info = self.debug_info();
+            self.display.backend.set_debug_info(info);
+        }
+        self.display.frame_tick();
+        self.display.present();
+        self.record_frame();
+
+        return status;
     }
 
-    pub fn start(&mut self, debug: bool) {
-        let tick = if debug { Self::debug_tick } else { Self::tick };
+    pub fn start(&mut self, debug: bool) -> ExecutionStatus {
+        self.set_debug(debug);
+        let frame_duration = Duration::from_nanos(1000000000 / FRAMES_PER_SECOND as u64);
 
         loop {
             let start = Instant::now();
 
-            tick(self);
+            if self.is_browsing() {
+                if !self.browse() {
+                    self.control.stop();
+                    return ExecutionStatus::Stopped;
+                }
+                sleep(frame_duration.saturating_sub(start.elapsed()));
+                continue;
+            }
+
+            if self.handle_hotkeys() {
+                self.display.present();
+                self.record_frame();
+                sleep(frame_duration.saturating_sub(start.elapsed()));
+                continue;
+            }
+
+            match self.run_frame() {
+                status @ (ExecutionStatus::Stopped
+                | ExecutionStatus::Exited
+                | ExecutionStatus::Error(_)) => return status,
+                ExecutionStatus::Running
+                | ExecutionStatus::WaitingForKey
+                | ExecutionStatus::WaitingForVblank
+                | ExecutionStatus::Paused => {}
+            }
+
+            sleep(frame_duration.saturating_sub(start.elapsed()));
+        }
+    }
+}
+
+// Turns name.png into the first of name-1.png, name-2.png, ... that does
+// not exist yet
+fn next_free_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+
+    return (1..)
+        .map(|n| path.with_file_name(format!("{stem}-{n}.{extension}")))
+        .find(|path| !path.exists())
+        .unwrap();
+}
 
-            let elapsed = ((1000000000 / CPU_INSTRUCTION_PER_SECOND) as u128)
-                .overflowing_sub(start.elapsed().as_nanos());
-            let sleep_duration = (elapsed.0 * !elapsed.1 as u128) as u64;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::HeadlessBackend;
 
-            sleep(time::Duration::from_nanos(sleep_duration));
+    #[test]
+    fn key_wait_keeps_frames_running() {
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        // LD V0, 0x0A; LD DT, V0; LD V1, K; JP 0x206
+        chip8
+            .load_rom(&[0x60, 0x0A, 0xF0, 0x15, 0xF1, 0x0A, 0x12, 0x06])
+            .unwrap();
+        chip8.set_instructions_per_frame(10);
+
+        assert_eq!(chip8.run_frame(), ExecutionStatus::WaitingForKey);
+        assert_eq!(chip8.cpu.delay_timer(), 0x0A);
+
+        // The timers keep counting down while FX0A waits
+        for _ in 0..3 {
+            assert_eq!(chip8.run_frame(), ExecutionStatus::WaitingForKey);
         }
+        assert_eq!(chip8.cpu.delay_timer(), 0x07);
+
+        chip8.input_backend_mut().queue_keys(&[0x5]);
+        assert_eq!(chip8.run_frame(), ExecutionStatus::Running);
+        assert_eq!(chip8.cpu.registers()[1], 0x5);
+        assert_eq!(chip8.cpu.delay_timer(), 0x06);
     }
 }
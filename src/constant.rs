@@ -1,19 +1,55 @@
 pub mod display {
-    pub const CLI_BACKEND_BUFFER_SIZE: usize = 2112;
+    pub const CLI_BACKEND_BUFFER_SIZE: usize = 8320;
     pub const CHIP8_DISPLAY_HEIGHT: usize = 32;
     pub const CHIP8_DISPLAY_WIDTH: usize = 64;
+    pub const SUPER_CHIP_DISPLAY_HEIGHT: usize = 64;
+    pub const SUPER_CHIP_DISPLAY_WIDTH: usize = 128;
 }
 
 pub mod cpu {
     pub const GENERAL_PURPOSE_REGISTERS_COUNT: usize = 16;
+    pub const STACK_SIZE: usize = 16;
+    pub const FLAG_REGISTERS_COUNT: usize = 16;
+    pub const AUDIO_PATTERN_SIZE: usize = 16;
+    pub const DEFAULT_PITCH: u8 = 64;
 }
 
 pub mod ram {
     pub const MEMORY_SIZE: usize = 4096;
+    pub const XO_CHIP_MEMORY_SIZE: usize = 65536;
     pub const FONT_LOCATION: usize = 0x50;
+    pub const BIG_FONT_LOCATION: usize = 0xA0;
     pub const ROM_START_LOCATION: usize = 0x200;
 }
 
 pub mod chip8 {
     pub const CPU_INSTRUCTION_PER_SECOND: usize = 700;
+    pub const FRAMES_PER_SECOND: usize = 60;
+}
+
+pub mod state {
+    pub const STATE_MAGIC: [u8; 4] = *b"C8ST";
+    pub const STATE_VERSION: u16 = 2;
+}
+
+pub mod rewind {
+    pub const REWIND_FRAMES: usize = 600;
+    pub const REWIND_MEMORY_LIMIT: usize = 4 * 1024 * 1024;
+}
+
+pub mod audio {
+    pub const DEFAULT_TONE_FREQUENCY: f32 = 440.0;
+    pub const DEFAULT_VOLUME: f32 = 0.25;
+    pub const WAV_SAMPLE_RATE: u32 = 44100;
+}
+
+pub mod browser {
+    pub const ROM_EXTENSIONS: [&str; 3] = ["ch8", "sc8", "xo8"];
+    pub const RECENT_ROMS_LIMIT: usize = 10;
+}
+
+pub mod debugger {
+    pub const DEBUGGER_PROMPT: &str = "(chip8) ";
+    pub const EXAMINE_LENGTH: usize = 0x10;
+    pub const EXAMINE_BYTES_PER_LINE: usize = 8;
 }
@@ -1,44 +1,64 @@
 use crate::{
     constant::{
-        cpu::GENERAL_PURPOSE_REGISTERS_COUNT,
-        display::{CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH},
-        ram::{FONT_LOCATION, MEMORY_SIZE},
+        cpu::{AUDIO_PATTERN_SIZE, DEFAULT_PITCH},
+        cpu::{FLAG_REGISTERS_COUNT, GENERAL_PURPOSE_REGISTERS_COUNT, STACK_SIZE},
+        ram::{BIG_FONT_LOCATION, FONT_LOCATION},
     },
     display::{Display, DisplayBackend},
+    input::InputBackend,
+    quirks::{IndexIncrement, Quirks},
+    state::{StateError, StateReader, StateWriter},
     timer::Timer,
 };
 use core::fmt;
 use std::fmt::Write;
 use std::hint::unreachable_unchecked;
+use std::ops::Range;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Instruction {
     ClearScreen(),
     Return(),
+    ScrollDown(u8),
+    ScrollUp(u8),
+    ScrollRight(),
+    ScrollLeft(),
+    Exit(),
+    LowResolution(),
+    HighResolution(),
     Jump(u16),
     CallSub(u16),
     SkipEq(u8, u8),
     SkipNEq(u8, u8),
     SkipRegEq(u8, u8),
+    StoreRange(u8, u8),
+    LoadRange(u8, u8),
     Set(u8, u8),
     Add(u8, u8),
     AluOperation { x: u8, y: u8, operation: AluOp },
     SkipRegNEq(u8, u8),
     SetIndex(u16),
+    SetIndexLong(),
     JumpWithOffset(u16),
     Random(u8, u8),
     Display { x: u8, y: u8, height: u8 },
     SkipIfPressed(u8),
     SkipIfNotPressed(u8),
+    SelectPlanes(u8),
+    LoadAudioPattern(),
     GetDelayTimer(u8),
     WaitForKey(u8),
     SetDelayTimer(u8),
     SetSoundTimer(u8),
+    SetPitch(u8),
     AddToIndex(u8),
     SetIndexToFontLocation(u8),
+    SetIndexToBigFontLocation(u8),
     BCDConversion(u8),
     Store(u8),
     Load(u8),
+    StoreFlags(u8),
+    LoadFlags(u8),
     Unknown(u16),
 }
 
@@ -55,13 +75,112 @@ pub enum AluOp {
     ShiftLeft,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExecutionError {
+    FetchOutOfBounds {
+        pc: u16,
+    },
+    UnknownInstruction {
+        pc: u16,
+        opcode: u16,
+    },
+    StackUnderflow {
+        pc: u16,
+        opcode: u16,
+    },
+    StackOverflow {
+        pc: u16,
+        opcode: u16,
+    },
+    MemoryOutOfBounds {
+        pc: u16,
+        opcode: u16,
+        address: usize,
+    },
+}
+
+impl ExecutionError {
+    pub fn pc(&self) -> u16 {
+        return match *self {
+            ExecutionError::FetchOutOfBounds { pc }
+            | ExecutionError::UnknownInstruction { pc, .. }
+            | ExecutionError::StackUnderflow { pc, .. }
+            | ExecutionError::StackOverflow { pc, .. }
+            | ExecutionError::MemoryOutOfBounds { pc, .. } => pc,
+        };
+    }
+
+    pub fn opcode(&self) -> Option<u16> {
+        return match *self {
+            ExecutionError::FetchOutOfBounds { .. } => None,
+            ExecutionError::UnknownInstruction { opcode, .. }
+            | ExecutionError::StackUnderflow { opcode, .. }
+            | ExecutionError::StackOverflow { opcode, .. }
+            | ExecutionError::MemoryOutOfBounds { opcode, .. } => Some(opcode),
+        };
+    }
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ExecutionError::FetchOutOfBounds { pc } => {
+                write!(f, "0x{pc:03X}: instruction fetch past the end of memory")
+            }
+            ExecutionError::UnknownInstruction { pc, opcode } => {
+                write!(f, "0x{pc:03X}: unknown instruction 0x{opcode:04X}")
+            }
+            ExecutionError::StackUnderflow { pc, opcode } => {
+                write!(f, "0x{pc:03X}: 0x{opcode:04X} returned with an empty stack")
+            }
+            ExecutionError::StackOverflow { pc, opcode } => write!(
+                f,
+                "0x{pc:03X}: 0x{opcode:04X} called more than {STACK_SIZE} nested subroutines"
+            ),
+            ExecutionError::MemoryOutOfBounds {
+                pc,
+                opcode,
+                address,
+            } => write!(
+                f,
+                "0x{pc:03X}: 0x{opcode:04X} accessed memory at 0x{address:X} which is out of bounds"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemoryAccess {
+    pub range: Range<usize>,
+    pub kind: AccessKind,
+}
+
 pub struct CPU {
     pub pc: u16,
     i: u16,
     registers: [u8; GENERAL_PURPOSE_REGISTERS_COUNT],
     stack: Vec<u16>,
+    flag_registers: [u8; FLAG_REGISTERS_COUNT],
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    pitch: u8,
     delay_timer: Timer,
     sound_timer: Timer,
+    key_wait_register: Option<u8>,
+    vblank_wait: bool,
+    exited: bool,
+    instruction_pc: u16,
+    opcode: u16,
+    // Memory used by the last instruction, its fetch included
+    memory_accesses: Vec<MemoryAccess>,
+    quirks: Quirks,
 }
 
 impl Default for CPU {
@@ -72,23 +191,229 @@ impl Default for CPU {
 
 impl CPU {
     pub fn new() -> Self {
+        return Self::with_quirks(Quirks::default());
+    }
+
+    pub fn with_quirks(quirks: Quirks) -> Self {
         return CPU {
             pc: 0,
             i: 0,
             registers: [0; GENERAL_PURPOSE_REGISTERS_COUNT],
             stack: Vec::new(),
+            flag_registers: [0; FLAG_REGISTERS_COUNT],
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
             delay_timer: Timer::new(),
             sound_timer: Timer::new(),
+            key_wait_register: None,
+            vblank_wait: false,
+            exited: false,
+            instruction_pc: 0,
+            opcode: 0,
+            memory_accesses: Vec::new(),
+            quirks,
         };
     }
 
-    pub fn fetch(&mut self, memory: [u8; MEMORY_SIZE]) -> u16 {
-        let instruction =
-            ((memory[self.pc as usize] as u16) << 8) | memory[(self.pc + 1) as usize] as u16;
+    pub fn quirks(&self) -> Quirks {
+        return self.quirks;
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
 
-        self.pc += 2;
+    pub fn is_waiting_for_key(&self) -> bool {
+        return self.key_wait_register.is_some();
+    }
 
-        return instruction;
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        return self.vblank_wait;
+    }
+
+    pub fn vblank(&mut self) {
+        self.vblank_wait = false;
+    }
+
+    pub fn audio_pattern(&self) -> [u8; AUDIO_PATTERN_SIZE] {
+        return self.audio_pattern;
+    }
+
+    pub fn pitch(&self) -> u8 {
+        return self.pitch;
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        return self.delay_timer.get_value();
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        return self.sound_timer.get_value();
+    }
+
+    pub fn index(&self) -> u16 {
+        return self.i;
+    }
+
+    pub fn registers(&self) -> &[u8; GENERAL_PURPOSE_REGISTERS_COUNT] {
+        return &self.registers;
+    }
+
+    pub fn stack_depth(&self) -> usize {
+        return self.stack.len();
+    }
+
+    // Return addresses, the innermost call last
+    pub fn stack(&self) -> &[u16] {
+        return &self.stack;
+    }
+
+    pub fn set_register(&mut self, x: usize, value: u8) {
+        self.registers[x] = value;
+    }
+
+    pub fn set_index(&mut self, index: u16) {
+        self.i = index;
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer.set_value(value);
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer.set_value(value);
+    }
+
+    pub fn memory_accesses(&self) -> &[MemoryAccess] {
+        return &self.memory_accesses;
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.delay_timer.tick();
+        self.sound_timer.tick();
+    }
+
+    pub fn has_exited(&self) -> bool {
+        return self.exited;
+    }
+
+    pub fn press_key(&mut self, key: u8) {
+        if let Some(x) = self.key_wait_register.take() {
+            self.registers[x as usize] = key;
+        }
+    }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.pc);
+        writer.write_u16(self.i);
+        writer.write_bytes(&self.registers);
+        writer.write_u8(self.stack.len() as u8);
+        for &address in self.stack.iter() {
+            writer.write_u16(address);
+        }
+        writer.write_bytes(&self.flag_registers);
+        writer.write_bytes(&self.audio_pattern);
+        writer.write_u8(self.pitch);
+        writer.write_u8(self.delay_timer.get_value());
+        writer.write_u8(self.sound_timer.get_value());
+        writer.write_u8(self.key_wait_register.unwrap_or(u8::MAX));
+        writer.write_bool(self.vblank_wait);
+        writer.write_bool(self.exited);
+        writer.write_u16(self.instruction_pc);
+        writer.write_u16(self.opcode);
+        self.quirks.save_state(writer);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, StateError> {
+        let mut cpu = CPU::new();
+
+        cpu.pc = reader.read_u16()?;
+        cpu.i = reader.read_u16()?;
+        cpu.registers = reader.read_array()?;
+
+        let stack_size = reader.read_u8()? as usize;
+        if stack_size > STACK_SIZE {
+            return Err(StateError::InvalidData("stack size"));
+        }
+        for _ in 0..stack_size {
+            cpu.stack.push(reader.read_u16()?);
+        }
+
+        cpu.flag_registers = reader.read_array()?;
+        cpu.audio_pattern = reader.read_array()?;
+        cpu.pitch = reader.read_u8()?;
+        cpu.delay_timer.set_value(reader.read_u8()?);
+        cpu.sound_timer.set_value(reader.read_u8()?);
+        cpu.key_wait_register = match reader.read_u8()? {
+            u8::MAX => None,
+            x if (x as usize) < GENERAL_PURPOSE_REGISTERS_COUNT => Some(x),
+            _ => return Err(StateError::InvalidData("key wait register")),
+        };
+        cpu.vblank_wait = reader.read_bool()?;
+        cpu.exited = reader.read_bool()?;
+        cpu.instruction_pc = reader.read_u16()?;
+        cpu.opcode = reader.read_u16()?;
+        cpu.quirks = Quirks::read_state(reader)?;
+
+        return Ok(cpu);
+    }
+
+    pub fn fetch(&mut self, memory: &[u8]) -> Result<u16, ExecutionError> {
+        let pc = self.pc as usize;
+        if pc + 1 >= memory.len() {
+            return Err(ExecutionError::FetchOutOfBounds { pc: self.pc });
+        }
+
+        let instruction = ((memory[pc] as u16) << 8) | memory[pc + 1] as u16;
+
+        self.memory_accesses.clear();
+        self.memory_accesses.push(MemoryAccess {
+            range: pc..pc + 2,
+            kind: AccessKind::Read,
+        });
+        self.instruction_pc = self.pc;
+        self.opcode = instruction;
+        self.advance(2)?;
+
+        return Ok(instruction);
+    }
+
+    // Moves PC forward, failing instead of wrapping around at the end of the
+    // 64 KiB XO-CHIP address space
+    fn advance(&mut self, bytes: u16) -> Result<(), ExecutionError> {
+        self.pc = self
+            .pc
+            .checked_add(bytes)
+            .ok_or(ExecutionError::FetchOutOfBounds {
+                pc: self.instruction_pc,
+            })?;
+
+        return Ok(());
+    }
+
+    // Checks and records an access of the current instruction
+    fn memory_range(
+        &mut self,
+        memory: &[u8],
+        start: usize,
+        length: usize,
+        kind: AccessKind,
+    ) -> Result<Range<usize>, ExecutionError> {
+        if start + length > memory.len() {
+            return Err(ExecutionError::MemoryOutOfBounds {
+                pc: self.instruction_pc,
+                opcode: self.opcode,
+                address: start.max(memory.len()),
+            });
+        }
+
+        let range = start..start + length;
+        self.memory_accesses.push(MemoryAccess {
+            range: range.clone(),
+            kind,
+        });
+
+        return Ok(range);
     }
 
     pub fn decode(instruction: u16) -> Instruction {
@@ -104,15 +429,27 @@ impl CPU {
 
         let instruction = match opcode {
             0x00 => match low_byte {
+                0xC0..=0xCF => Instruction::ScrollDown(low_byte & 0x0F),
+                0xD0..=0xDF => Instruction::ScrollUp(low_byte & 0x0F),
                 0xE0 => Instruction::ClearScreen(),
                 0xEE => Instruction::Return(),
+                0xFB => Instruction::ScrollRight(),
+                0xFC => Instruction::ScrollLeft(),
+                0xFD => Instruction::Exit(),
+                0xFE => Instruction::LowResolution(),
+                0xFF => Instruction::HighResolution(),
                 _ => Instruction::Unknown(instruction),
             },
             0x10 => Instruction::Jump(nnn),
             0x20 => Instruction::CallSub(nnn),
             0x30 => Instruction::SkipEq(x, nn),
             0x40 => Instruction::SkipNEq(x, nn),
-            0x50 => Instruction::SkipRegEq(x, y),
+            0x50 => match low_byte & 0x0F {
+                0x0 => Instruction::SkipRegEq(x, y),
+                0x2 => Instruction::StoreRange(x, y),
+                0x3 => Instruction::LoadRange(x, y),
+                _ => Instruction::Unknown(instruction),
+            },
             0x60 => Instruction::Set(x, nn),
             0x70 => Instruction::Add(x, nn),
             0x80 => Instruction::AluOperation {
@@ -146,15 +483,22 @@ impl CPU {
                 _ => Instruction::Unknown(instruction),
             },
             0xF0 => match low_byte {
+                0x00 if x == 0 => Instruction::SetIndexLong(),
+                0x01 => Instruction::SelectPlanes(x),
+                0x02 if x == 0 => Instruction::LoadAudioPattern(),
                 0x07 => Instruction::GetDelayTimer(x),
                 0x0A => Instruction::WaitForKey(x),
                 0x15 => Instruction::SetDelayTimer(x),
                 0x18 => Instruction::SetSoundTimer(x),
                 0x1E => Instruction::AddToIndex(x),
+                0x3A => Instruction::SetPitch(x),
                 0x29 => Instruction::SetIndexToFontLocation(x),
+                0x30 => Instruction::SetIndexToBigFontLocation(x),
                 0x33 => Instruction::BCDConversion(x),
                 0x55 => Instruction::Store(x),
                 0x65 => Instruction::Load(x),
+                0x75 => Instruction::StoreFlags(x),
+                0x85 => Instruction::LoadFlags(x),
                 _ => Instruction::Unknown(instruction),
             },
             _ => unsafe { unreachable_unchecked() },
@@ -163,41 +507,77 @@ impl CPU {
         return instruction;
     }
 
-    pub fn execute<B: DisplayBackend>(
+    pub fn execute<D: DisplayBackend, I: InputBackend>(
         &mut self,
         instruction: Instruction,
-        memory: &mut [u8; MEMORY_SIZE],
-        display: &mut Display<B>,
-    ) {
+        memory: &mut [u8],
+        display: &mut Display<D>,
+        input: &mut I,
+    ) -> Result<(), ExecutionError> {
         match instruction {
-            Instruction::ClearScreen() => {
-                for row in display.pixels.iter_mut() {
-                    for pixel in row.iter_mut() {
-                        *pixel = false;
-                    }
-                }
-            }
+            Instruction::ClearScreen() => display.clear(),
             Instruction::Return() => {
-                self.pc = self.stack.pop().expect("Return while stack is empty.")
+                self.pc = self.stack.pop().ok_or(ExecutionError::StackUnderflow {
+                    pc: self.instruction_pc,
+                    opcode: self.opcode,
+                })?;
             }
+            Instruction::ScrollDown(n) => display.scroll_down(n as usize),
+            Instruction::ScrollUp(n) => display.scroll_up(n as usize),
+            Instruction::ScrollRight() => display.scroll_right(4),
+            Instruction::ScrollLeft() => display.scroll_left(4),
+            Instruction::Exit() => self.exited = true,
+            Instruction::LowResolution() => display.set_high_resolution(false),
+            Instruction::HighResolution() => display.set_high_resolution(true),
             Instruction::Jump(nnn) => self.pc = nnn,
             Instruction::CallSub(nnn) => {
+                if self.stack.len() >= STACK_SIZE {
+                    return Err(ExecutionError::StackOverflow {
+                        pc: self.instruction_pc,
+                        opcode: self.opcode,
+                    });
+                }
+
                 self.stack.push(self.pc);
                 self.pc = nnn;
             }
             Instruction::SkipEq(x, nn) => {
                 if self.registers[x as usize] == nn {
-                    self.pc += 2;
+                    self.skip(memory)?;
                 }
             }
             Instruction::SkipNEq(x, nn) => {
                 if self.registers[x as usize] != nn {
-                    self.pc += 2;
+                    self.skip(memory)?;
                 }
             }
             Instruction::SkipRegEq(x, y) => {
                 if self.registers[x as usize] == self.registers[y as usize] {
-                    self.pc += 2;
+                    self.skip(memory)?;
+                }
+            }
+            Instruction::StoreRange(x, y) => {
+                let range = self.memory_range(
+                    memory,
+                    self.i as usize,
+                    x.abs_diff(y) as usize + 1,
+                    AccessKind::Write,
+                )?;
+
+                for (address, register) in range.zip(Self::register_range(x, y)) {
+                    memory[address] = self.registers[register];
+                }
+            }
+            Instruction::LoadRange(x, y) => {
+                let range = self.memory_range(
+                    memory,
+                    self.i as usize,
+                    x.abs_diff(y) as usize + 1,
+                    AccessKind::Read,
+                )?;
+
+                for (address, register) in range.zip(Self::register_range(x, y)) {
+                    self.registers[register] = memory[address];
                 }
             }
             Instruction::Set(x, nn) => self.registers[x as usize] = nn,
@@ -206,9 +586,18 @@ impl CPU {
             }
             Instruction::AluOperation { x, y, operation } => match operation {
                 AluOp::LoadRegReg => self.registers[x as usize] = self.registers[y as usize],
-                AluOp::Or => self.registers[x as usize] |= self.registers[y as usize],
-                AluOp::And => self.registers[x as usize] &= self.registers[y as usize],
-                AluOp::Xor => self.registers[x as usize] ^= self.registers[y as usize],
+                AluOp::Or => {
+                    self.registers[x as usize] |= self.registers[y as usize];
+                    self.reset_vf();
+                }
+                AluOp::And => {
+                    self.registers[x as usize] &= self.registers[y as usize];
+                    self.reset_vf();
+                }
+                AluOp::Xor => {
+                    self.registers[x as usize] ^= self.registers[y as usize];
+                    self.reset_vf();
+                }
                 AluOp::AddRegReg => {
                     let overflow;
                     (self.registers[x as usize], overflow) =
@@ -224,8 +613,10 @@ impl CPU {
                     self.registers[0xF] = !overflow as u8;
                 }
                 AluOp::ShiftRight => {
-                    self.registers[y as usize] = ((self.registers[x as usize] & 0x01) == 1) as u8;
-                    self.registers[x as usize] >>= 1;
+                    let value = self.shift_operand(x, y);
+
+                    self.registers[x as usize] = value >> 1;
+                    self.registers[0xF] = value & 0x01;
                 }
                 AluOp::SubNeg => {
                     let overflow;
@@ -235,103 +626,209 @@ impl CPU {
                     self.registers[0xF] = !overflow as u8;
                 }
                 AluOp::ShiftLeft => {
-                    self.registers[y as usize] =
-                        ((self.registers[x as usize] & 0x80) == 0x80) as u8;
-                    self.registers[x as usize] <<= 1;
+                    let value = self.shift_operand(x, y);
+
+                    self.registers[x as usize] = value << 1;
+                    self.registers[0xF] = value >> 7;
                 }
             },
             Instruction::SkipRegNEq(x, y) => {
                 if self.registers[x as usize] != self.registers[y as usize] {
-                    self.pc += 2;
+                    self.skip(memory)?;
                 }
             }
             Instruction::SetIndex(nnn) => self.i = nnn,
-            Instruction::JumpWithOffset(nnn) => self.pc = nnn + self.registers[0x0] as u16,
+            Instruction::SetIndexLong() => {
+                let range = self.memory_range(memory, self.pc as usize, 2, AccessKind::Read)?;
+
+                self.i = u16::from_be_bytes([memory[range.start], memory[range.start + 1]]);
+                self.advance(2)?;
+            }
+            Instruction::JumpWithOffset(nnn) => {
+                let offset_register = if self.quirks.jump_uses_vx {
+                    (nnn >> 8) as usize
+                } else {
+                    0x0
+                };
+
+                self.pc = nnn + self.registers[offset_register] as u16;
+            }
             Instruction::Random(x, nn) => self.registers[x as usize] = fastrand::u8(..) & nn,
             Instruction::Display { x, y, height } => {
-                let x_cord = (self.registers[x as usize] % CHIP8_DISPLAY_WIDTH as u8) as usize;
-                let y_cord = (self.registers[y as usize] % CHIP8_DISPLAY_HEIGHT as u8) as usize;
+                let (sprite_width, sprite_height) = match height {
+                    0 if self.quirks.large_sprites => (16, 16),
+                    _ => (8, height as usize),
+                };
+                let bytes_per_row = sprite_width / 8;
+                let (display_width, display_height) = (display.width(), display.height());
 
-                self.registers[0xF] = 0;
+                let x_cord = self.registers[x as usize] as usize % display_width;
+                let y_cord = self.registers[y as usize] as usize % display_height;
 
-                for n in 0..height as usize {
-                    if y_cord + n >= CHIP8_DISPLAY_HEIGHT {
-                        break;
-                    }
+                let plane_size = sprite_height * bytes_per_row;
+                let planes = display.planes();
+                let range = self.memory_range(
+                    memory,
+                    self.i as usize,
+                    plane_size * planes.count_ones() as usize,
+                    AccessKind::Read,
+                )?;
 
-                    let row = memory[self.i as usize + n];
+                self.registers[0xF] = 0;
+
+                let selected_planes = [0b01, 0b10].into_iter().filter(|plane| planes & plane != 0);
+                // Without large sprites DXY0 has no rows and the range is empty
+                let sprites = memory[range].chunks_exact(plane_size.max(1));
+                for (plane, sprite) in selected_planes.zip(sprites) {
+                    for (n, row) in sprite.chunks_exact(bytes_per_row).enumerate() {
+                        let row = row.iter().fold(0u16, |row, &byte| (row << 8) | byte as u16);
 
-                    for m in 0..8 {
-                        if x_cord + m >= CHIP8_DISPLAY_WIDTH {
-                            break;
+                        let mut pixel_y = y_cord + n;
+                        if pixel_y >= display_height {
+                            if self.quirks.clip_sprites {
+                                break;
+                            }
+                            pixel_y %= display_height;
                         }
 
-                        let bit = ((row >> (7 - m)) & 0x01) == 1;
+                        for m in 0..sprite_width {
+                            let mut pixel_x = x_cord + m;
+                            if pixel_x >= display_width {
+                                if self.quirks.clip_sprites {
+                                    break;
+                                }
+                                pixel_x %= display_width;
+                            }
+
+                            let bit = ((row >> (sprite_width - 1 - m)) & 0x01) == 1;
 
-                        if bit {
-                            if display.pixels[y_cord + n][x_cord + m] {
+                            if bit && display.toggle(pixel_x, pixel_y, plane) {
                                 self.registers[0xF] = 1;
                             }
-
-                            display.pixels[y_cord + n][x_cord + m] =
-                                !display.pixels[y_cord + n][x_cord + m];
                         }
                     }
                 }
 
-                display.render();
+                self.vblank_wait = self.quirks.display_wait;
+
+                display.mark_dirty();
             }
             Instruction::SkipIfPressed(x) => {
-                let pressed_keys = display.read_keys();
+                let pressed_keys = input.read_keys();
                 if pressed_keys.contains(&self.registers[x as usize]) {
-                    self.pc += 2;
+                    self.skip(memory)?;
                 }
             }
             Instruction::SkipIfNotPressed(x) => {
-                let pressed_keys = display.read_keys();
+                let pressed_keys = input.read_keys();
                 if !pressed_keys.contains(&self.registers[x as usize]) {
-                    self.pc += 2;
+                    self.skip(memory)?;
                 }
             }
+            Instruction::SelectPlanes(n) => display.select_planes(n),
+            Instruction::LoadAudioPattern() => {
+                let range = self.memory_range(
+                    memory,
+                    self.i as usize,
+                    AUDIO_PATTERN_SIZE,
+                    AccessKind::Read,
+                )?;
+
+                self.audio_pattern.copy_from_slice(&memory[range]);
+            }
             Instruction::GetDelayTimer(x) => {
                 self.registers[x as usize] = self.delay_timer.get_value()
             }
-            Instruction::WaitForKey(x) => {
-                let key = display.wait_for_key();
-                self.registers[x as usize] = key;
-            }
+            Instruction::WaitForKey(x) => self.key_wait_register = Some(x),
             Instruction::SetDelayTimer(x) => self.delay_timer.set_value(self.registers[x as usize]),
             Instruction::SetSoundTimer(x) => self.sound_timer.set_value(self.registers[x as usize]),
+            Instruction::SetPitch(x) => self.pitch = self.registers[x as usize],
             Instruction::AddToIndex(x) => {
-                let old_i = self.i;
-                self.i += self.registers[x as usize] as u16;
-
-                if self.i > 0xFFF && old_i <= 0xFFF {
-                    self.registers[x as usize] = 1;
-                }
+                self.i = self.i.wrapping_add(self.registers[x as usize] as u16)
+            }
+            Instruction::SetIndexToFontLocation(x) => {
+                let digit = (self.registers[x as usize] & 0x0F) as u16;
+                self.i = digit * 5 + FONT_LOCATION as u16;
+            }
+            Instruction::SetIndexToBigFontLocation(x) => {
+                let digit = (self.registers[x as usize] & 0x0F) as u16;
+                self.i = digit * 10 + BIG_FONT_LOCATION as u16;
             }
-            Instruction::SetIndexToFontLocation(x) => self.i = x as u16 * 5 + FONT_LOCATION as u16,
             Instruction::BCDConversion(x) => {
                 let vx = self.registers[x as usize];
-                let i = self.i as usize;
+                let range = self.memory_range(memory, self.i as usize, 3, AccessKind::Write)?;
 
-                memory[i] = vx / 100;
-                memory[i + i] = (vx / 10) % 10;
-                memory[i + 2] = vx % 10;
+                memory[range].copy_from_slice(&[vx / 100, (vx / 10) % 10, vx % 10]);
             }
             Instruction::Store(x) => {
-                let i: usize = self.i.into();
+                let range =
+                    self.memory_range(memory, self.i as usize, x as usize + 1, AccessKind::Write)?;
 
-                memory[i..=(i + x as usize)].copy_from_slice(&self.registers[0..=x as usize]);
+                memory[range].copy_from_slice(&self.registers[0..=x as usize]);
+                self.increment_index(x);
             }
             Instruction::Load(x) => {
-                let i: usize = self.i.into();
+                let range =
+                    self.memory_range(memory, self.i as usize, x as usize + 1, AccessKind::Read)?;
 
-                self.registers[0..=x as usize].copy_from_slice(&memory[i..=(i + x as usize)]);
+                self.registers[0..=x as usize].copy_from_slice(&memory[range]);
+                self.increment_index(x);
+            }
+            Instruction::StoreFlags(x) => {
+                self.flag_registers[0..=x as usize].copy_from_slice(&self.registers[0..=x as usize])
+            }
+            Instruction::LoadFlags(x) => {
+                self.registers[0..=x as usize].copy_from_slice(&self.flag_registers[0..=x as usize])
+            }
+            Instruction::Unknown(opcode) => {
+                return Err(ExecutionError::UnknownInstruction {
+                    pc: self.instruction_pc,
+                    opcode,
+                });
             }
-            Instruction::Unknown(instruction) => panic!("Unknown instruction: {:X}", instruction),
+        }
+
+        return Ok(());
+    }
+
+    // Skips over the 4-byte F000 NNNN instruction as a whole
+    fn skip(&mut self, memory: &[u8]) -> Result<(), ExecutionError> {
+        let pc = self.pc as usize;
+        let next_is_long = memory.get(pc..pc + 2) == Some(&[0xF0, 0x00]);
+
+        return self.advance(if next_is_long { 4 } else { 2 });
+    }
+
+    // Registers from VX to VY, counting down when X is larger than Y
+    fn register_range(x: u8, y: u8) -> impl Iterator<Item = usize> {
+        return (0..=x.abs_diff(y) as usize).map(move |n| {
+            if x <= y {
+                x as usize + n
+            } else {
+                x as usize - n
+            }
+        });
+    }
+
+    fn reset_vf(&mut self) {
+        if self.quirks.vf_reset {
+            self.registers[0xF] = 0;
         }
     }
+
+    fn shift_operand(&self, x: u8, y: u8) -> u8 {
+        let source = if self.quirks.shift_uses_vy { y } else { x };
+
+        return self.registers[source as usize];
+    }
+
+    fn increment_index(&mut self, x: u8) {
+        self.i = self.i.wrapping_add(match self.quirks.index_increment {
+            IndexIncrement::Unchanged => 0,
+            IndexIncrement::X => x as u16,
+            IndexIncrement::XPlusOne => x as u16 + 1,
+        });
+    }
 }
 
 impl fmt::Display for CPU {
@@ -355,11 +852,20 @@ impl fmt::Display for Instruction {
         match self {
             Instruction::ClearScreen() => write!(f, "CLS"),
             Instruction::Return() => write!(f, "RET"),
+            Instruction::ScrollDown(n) => write!(f, "SCD {n:X}"),
+            Instruction::ScrollUp(n) => write!(f, "SCU {n:X}"),
+            Instruction::ScrollRight() => write!(f, "SCR"),
+            Instruction::ScrollLeft() => write!(f, "SCL"),
+            Instruction::Exit() => write!(f, "EXIT"),
+            Instruction::LowResolution() => write!(f, "LOW"),
+            Instruction::HighResolution() => write!(f, "HIGH"),
             Instruction::Jump(nnn) => write!(f, "JP 0x{nnn:X}"),
             Instruction::CallSub(nnn) => write!(f, "CALL 0x{nnn:X}"),
             Instruction::SkipEq(x, nn) => write!(f, "SE V{x:X}, 0x{nn:X}"),
             Instruction::SkipNEq(x, nn) => write!(f, "SNE V{x:X}, 0x{nn:X}"),
             Instruction::SkipRegEq(x, y) => write!(f, "SE V{x:X}, V{y:X}"),
+            Instruction::StoreRange(x, y) => write!(f, "LD [I], V{x:X}-V{y:X}"),
+            Instruction::LoadRange(x, y) => write!(f, "LD V{x:X}-V{y:X}, [I]"),
             Instruction::Set(x, nn) => write!(f, "LD V{x:X}, 0x{nn:X}"),
             Instruction::Add(x, nn) => write!(f, "ADD V{x:X}, 0x{nn:X}"),
             Instruction::AluOperation { x, y, operation } => match operation {
@@ -375,37 +881,67 @@ impl fmt::Display for Instruction {
             },
             Instruction::SkipRegNEq(x, y) => write!(f, "SNE V{x:X}, V{y:X}"),
             Instruction::SetIndex(nnn) => write!(f, "LD I, 0x{nnn:X}"),
+            Instruction::SetIndexLong() => write!(f, "LD I, LONG"),
             Instruction::JumpWithOffset(nnn) => write!(f, "JP V0, 0x{nnn:X}"),
             Instruction::Random(x, nn) => write!(f, "RND V{x:X}, 0x{nn:X}"),
             Instruction::Display { x, y, height } => write!(f, "DRW V{x:X}, V{y:X}, {height:X}"),
             Instruction::SkipIfPressed(x) => write!(f, "SKP V{x:X}"),
             Instruction::SkipIfNotPressed(x) => write!(f, "SKNP V{x:X}"),
+            Instruction::SelectPlanes(n) => write!(f, "PLANE {n:X}"),
+            Instruction::LoadAudioPattern() => write!(f, "AUDIO"),
             Instruction::GetDelayTimer(x) => write!(f, "LD V{x:X}, DT"),
             Instruction::WaitForKey(x) => write!(f, "LD V{x:X}, K"),
             Instruction::SetDelayTimer(x) => write!(f, "LD DT, V{x:X}"),
             Instruction::SetSoundTimer(x) => write!(f, "LD ST, V{x:X}"),
+            Instruction::SetPitch(x) => write!(f, "PITCH V{x:X}"),
             Instruction::AddToIndex(x) => write!(f, "ADD I, V{x:X}"),
             Instruction::SetIndexToFontLocation(x) => write!(f, "LD F, V{x:X}"),
+            Instruction::SetIndexToBigFontLocation(x) => write!(f, "LD HF, V{x:X}"),
             Instruction::BCDConversion(x) => write!(f, "LD B, V{x:X}"),
             Instruction::Store(x) => write!(f, "LD [I], V{x:X}"),
             Instruction::Load(x) => write!(f, "LD V{x:X}, [I]"),
+            Instruction::StoreFlags(x) => write!(f, "LD R, V{x:X}"),
+            Instruction::LoadFlags(x) => write!(f, "LD V{x:X}, R"),
             Instruction::Unknown(instruction) => write!(f, ".dw 0x{instruction:X}"),
         }
     }
 }
 
+// The instruction at an address, reading two words so that the long form of
+// LD I is shown whole
+pub fn disassemble_at(memory: &[u8], address: usize) -> String {
+    let words = &memory[address.min(memory.len())..(address + 4).min(memory.len())];
+
+    return disassemble(words)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+}
+
 pub fn disassemble(rom_data: &[u8]) -> String {
     let mut result = String::new();
 
-    rom_data.chunks_exact(2).for_each(|instruction| {
-        let instruction = ((instruction[0] as u16) << 8) | instruction[1] as u16;
+    let mut words = rom_data
+        .chunks_exact(2)
+        .map(|word| ((word[0] as u16) << 8) | word[1] as u16);
+
+    while let Some(instruction) = words.next() {
         let instruction = CPU::decode(instruction);
 
+        if instruction == Instruction::SetIndexLong() {
+            match words.next() {
+                Some(address) => writeln!(result, "LD I, 0x{address:X}").unwrap(),
+                None => writeln!(result, ".dw 0xF000").unwrap(),
+            }
+            continue;
+        }
+
         writeln!(result, "{instruction}").unwrap();
-    });
+    }
 
     if rom_data.len() % 2 == 1 {
-        writeln!(result, ".db 0x{}", rom_data.last().unwrap()).unwrap();
+        writeln!(result, ".db 0x{:X}", rom_data.last().unwrap()).unwrap();
     }
 
     return result;
@@ -414,26 +950,33 @@ pub fn disassemble(rom_data: &[u8]) -> String {
 #[cfg(test)]
 mod tests {
     use crate::{
+        chip8::{CHIP8, ExecutionStatus},
         constant::{
-            display::{CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH},
-            ram::FONT_LOCATION,
+            display::{
+                CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH, SUPER_CHIP_DISPLAY_HEIGHT,
+                SUPER_CHIP_DISPLAY_WIDTH,
+            },
+            ram::{BIG_FONT_LOCATION, FONT_LOCATION},
         },
-        cpu::Instruction::*,
-        display::{CLIBackend, Display},
+        cpu::{ExecutionError, Instruction::*},
+        display::{Display, HeadlessBackend},
+        quirks::{IndexIncrement, Platform, Quirks},
         ram::Ram,
     };
 
-    use super::CPU;
+    use super::{AluOp, CPU, disassemble};
 
     #[test]
     fn cpu_execution() {
         let mut ram = Ram::new();
-        let mut display = Display::new(CLIBackend::default());
-        let mut cpu = CPU::new();
+        let mut display = Display::new(HeadlessBackend::new());
+        let mut input = HeadlessBackend::new();
+        let mut cpu = CPU::with_quirks(Platform::SuperChip11.quirks());
 
         macro_rules! execute {
             ($instruction:expr) => {
-                cpu.execute($instruction, &mut ram.memory, &mut display);
+                cpu.execute($instruction, &mut ram.memory, &mut display, &mut input)
+                    .unwrap();
             };
         }
 
@@ -476,7 +1019,8 @@ mod tests {
         assert_eq!(cpu.pc, 6);
 
         for i in 0..=0xF {
-            execute!(SetIndexToFontLocation(i as u8));
+            execute!(Set(0x3, i as u8));
+            execute!(SetIndexToFontLocation(0x3));
             assert_eq!(cpu.i, FONT_LOCATION as u16 + i * 0x5);
         }
 
@@ -520,13 +1064,392 @@ mod tests {
             assert!(cpu.registers[i as usize] <= 0x0F);
         }
 
-        for i in (0..display.pixels.as_flattened().len()).step_by(3) {
-            display.pixels.as_flattened_mut()[i] = true;
+        for i in (0..display.pixels.len()).step_by(3) {
+            display.pixels[i] = 1;
+        }
+        execute!(ClearScreen());
+        assert_eq!(
+            display.pixels,
+            vec![0; CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT]
+        );
+    }
+
+    #[test]
+    fn execution_errors() {
+        let mut ram = Ram::new();
+        let mut display = Display::new(HeadlessBackend::new());
+        let mut input = HeadlessBackend::new();
+        let mut cpu = CPU::new();
+
+        macro_rules! execute {
+            ($instruction:expr) => {
+                cpu.execute($instruction, &mut ram.memory, &mut display, &mut input)
+            };
+        }
+
+        assert_eq!(
+            execute!(Unknown(0x8008)),
+            Err(ExecutionError::UnknownInstruction {
+                pc: 0,
+                opcode: 0x8008
+            })
+        );
+
+        assert_eq!(
+            execute!(Return()),
+            Err(ExecutionError::StackUnderflow { pc: 0, opcode: 0 })
+        );
+
+        for _ in 0..16 {
+            execute!(CallSub(0x200)).unwrap();
+        }
+        assert_eq!(
+            execute!(CallSub(0x200)),
+            Err(ExecutionError::StackOverflow { pc: 0, opcode: 0 })
+        );
+
+        ram.memory[0xFFE] = 0xFF;
+        ram.memory[0xFFF] = 0x55;
+        cpu.pc = 0xFFE;
+        assert_eq!(cpu.fetch(&ram.memory), Ok(0xFF55));
+        assert_eq!(
+            execute!(SetIndex(0xFFF)).and_then(|_| execute!(Store(1))),
+            Err(ExecutionError::MemoryOutOfBounds {
+                pc: 0xFFE,
+                opcode: 0xFF55,
+                address: 0x1000
+            })
+        );
+        assert_eq!(
+            cpu.fetch(&ram.memory),
+            Err(ExecutionError::FetchOutOfBounds { pc: 0x1000 })
+        );
+
+        for instruction in [
+            Load(1),
+            BCDConversion(0),
+            super::Instruction::Display {
+                x: 0,
+                y: 0,
+                height: 2,
+            },
+        ] {
+            assert!(matches!(
+                execute!(instruction),
+                Err(ExecutionError::MemoryOutOfBounds {
+                    address: 0x1000,
+                    ..
+                })
+            ));
+        }
+        assert_eq!(ram.memory[0xFFF], 0x55);
+    }
+
+    #[test]
+    fn end_of_xo_chip_memory() {
+        // LD V0, 0x0 up to the last word of memory
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        chip8.set_platform(Platform::XoChip);
+        chip8
+            .load_rom(&[0x60, 0x00].repeat((0x10000 - 0x200) / 2))
+            .unwrap();
+        assert_eq!(
+            chip8.run_cycles(0x8000),
+            ExecutionStatus::Error(ExecutionError::FetchOutOfBounds { pc: 0xFFFE })
+        );
+
+        let mut ram = Ram::with_size(Platform::XoChip.memory_size());
+        let mut display = Display::new(HeadlessBackend::new());
+        let mut input = HeadlessBackend::new();
+        let mut cpu = CPU::new();
+
+        // Skipping and reading the operand of F000 NNNN from the last word
+        for instruction in [SkipEq(0, 0), SetIndexLong()] {
+            cpu.pc = 0xFFFC;
+            cpu.fetch(&ram.memory).unwrap();
+            assert_eq!(
+                cpu.execute(instruction, &mut ram.memory, &mut display, &mut input),
+                Err(ExecutionError::FetchOutOfBounds { pc: 0xFFFC })
+            );
+        }
+    }
+
+    #[test]
+    fn quirks() {
+        let mut ram = Ram::new();
+        let mut display = Display::new(HeadlessBackend::new());
+        let mut input = HeadlessBackend::new();
+        let mut cpu = CPU::with_quirks(Platform::CosmacVip.quirks());
+
+        macro_rules! execute {
+            ($instruction:expr) => {
+                cpu.execute($instruction, &mut ram.memory, &mut display, &mut input)
+                    .unwrap();
+            };
+        }
+        macro_rules! alu {
+            ($x:expr, $y:expr, $operation:ident) => {
+                execute!(AluOperation {
+                    x: $x,
+                    y: $y,
+                    operation: AluOp::$operation
+                });
+            };
+        }
+
+        execute!(Set(0x1, 0x81));
+        execute!(Set(0x2, 0x03));
+        alu!(0x1, 0x2, ShiftRight);
+        assert_eq!((cpu.registers[0x1], cpu.registers[0xF]), (0x01, 1));
+        cpu.set_quirks(Platform::SuperChip11.quirks());
+        execute!(Set(0x1, 0x81));
+        alu!(0x1, 0x2, ShiftLeft);
+        assert_eq!((cpu.registers[0x1], cpu.registers[0xF]), (0x02, 1));
+
+        for (vf_reset, expected_vf) in [(true, 0), (false, 1)] {
+            cpu.set_quirks(Quirks {
+                vf_reset,
+                ..Quirks::default()
+            });
+            execute!(Set(0xF, 1));
+            alu!(0x1, 0x2, Or);
+            assert_eq!(cpu.registers[0xF], expected_vf);
         }
+
+        for (index_increment, expected_i) in [
+            (IndexIncrement::Unchanged, 0x300),
+            (IndexIncrement::X, 0x303),
+            (IndexIncrement::XPlusOne, 0x304),
+        ] {
+            cpu.set_quirks(Quirks {
+                index_increment,
+                ..Quirks::default()
+            });
+            execute!(SetIndex(0x300));
+            execute!(Store(3));
+            assert_eq!(cpu.i, expected_i);
+            execute!(SetIndex(0x300));
+            execute!(Load(3));
+            assert_eq!(cpu.i, expected_i);
+        }
+
+        execute!(Set(0x0, 0x10));
+        execute!(Set(0x3, 0x20));
+        cpu.set_quirks(Platform::CosmacVip.quirks());
+        execute!(JumpWithOffset(0x300));
+        assert_eq!(cpu.pc, 0x310);
+        cpu.set_quirks(Platform::Chip48.quirks());
+        execute!(JumpWithOffset(0x300));
+        assert_eq!(cpu.pc, 0x320);
+
+        let sprite = super::Instruction::Display {
+            x: 0x4,
+            y: 0x5,
+            height: 1,
+        };
+        ram.memory[0x300] = 0xFF;
+        execute!(SetIndex(0x300));
+        execute!(Set(0x4, CHIP8_DISPLAY_WIDTH as u8 - 4));
+        execute!(Set(0x5, 0));
+        for clip_sprites in [true, false] {
+            cpu.set_quirks(Quirks {
+                clip_sprites,
+                ..Quirks::default()
+            });
+            execute!(ClearScreen());
+            execute!(sprite);
+            assert_eq!(display.pixels[0..4], [!clip_sprites as u8; 4]);
+            assert_eq!(
+                display.pixels[CHIP8_DISPLAY_WIDTH - 4..CHIP8_DISPLAY_WIDTH],
+                [1; 4]
+            );
+        }
+
+        for display_wait in [true, false] {
+            cpu.set_quirks(Quirks {
+                display_wait,
+                ..Quirks::default()
+            });
+            execute!(sprite);
+            assert_eq!(cpu.is_waiting_for_vblank(), display_wait);
+            cpu.vblank();
+            assert!(!cpu.is_waiting_for_vblank());
+        }
+
+        // DXY0 is not a 16x16 sprite before SUPER-CHIP
+        ram.memory[0x300..0x320].fill(0xFF);
         execute!(ClearScreen());
+        cpu.set_quirks(Platform::CosmacVip.quirks());
+        execute!(super::Instruction::Display {
+            x: 0x4,
+            y: 0x5,
+            height: 0,
+        });
+        assert_eq!(cpu.registers[0xF], 0);
+        assert!(display.pixels.iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn super_chip() {
+        let mut ram = Ram::new();
+        let mut display = Display::new(HeadlessBackend::new());
+        let mut input = HeadlessBackend::new();
+        let mut cpu = CPU::with_quirks(Platform::SuperChip11.quirks());
+
+        macro_rules! execute {
+            ($instruction:expr) => {
+                cpu.execute($instruction, &mut ram.memory, &mut display, &mut input)
+                    .unwrap();
+            };
+        }
+
+        assert_eq!(CPU::decode(0x00FF), HighResolution());
+        assert_eq!(CPU::decode(0x00C3), ScrollDown(3));
+        assert_eq!(
+            CPU::decode(0xD120),
+            super::Instruction::Display {
+                x: 1,
+                y: 2,
+                height: 0
+            }
+        );
+
+        execute!(HighResolution());
+        assert!(display.is_high_resolution());
+        assert_eq!(
+            display.pixels.len(),
+            SUPER_CHIP_DISPLAY_WIDTH * SUPER_CHIP_DISPLAY_HEIGHT
+        );
+
+        ram.memory[0x300..0x320].fill(0xFF);
+        execute!(SetIndex(0x300));
+        execute!(Set(0x0, SUPER_CHIP_DISPLAY_WIDTH as u8 - 16));
+        execute!(Set(0x1, 0));
+        let sprite = super::Instruction::Display {
+            x: 0x0,
+            y: 0x1,
+            height: 0,
+        };
+        execute!(sprite);
+        assert_eq!(cpu.registers[0xF], 0);
+        assert_eq!(
+            display.pixels.iter().filter(|&&pixel| pixel != 0).count(),
+            16 * 16
+        );
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 1, 15), 1);
+        execute!(sprite);
+        assert_eq!(cpu.registers[0xF], 1);
+        execute!(sprite);
+
+        execute!(ScrollDown(4));
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 1, 3), 0);
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 1, 19), 1);
+        execute!(ScrollLeft());
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 1, 4), 0);
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 5, 4), 1);
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 20, 4), 1);
+        execute!(ScrollRight());
+        execute!(ScrollRight());
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 1, 4), 1);
+        assert_eq!(display.pixel(SUPER_CHIP_DISPLAY_WIDTH - 17, 4), 0);
+
+        execute!(LowResolution());
+        assert!(!display.is_high_resolution());
         assert_eq!(
             display.pixels,
-            [[false; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT]
+            vec![0; CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT]
+        );
+
+        execute!(Set(0x2, 0x9));
+        execute!(SetIndexToBigFontLocation(0x2));
+        assert_eq!(cpu.i, BIG_FONT_LOCATION as u16 + 90);
+
+        for i in 0..8 {
+            execute!(Set(i, i + 1));
+        }
+        execute!(StoreFlags(7));
+        for i in 0..8 {
+            execute!(Set(i, 0));
+        }
+        execute!(LoadFlags(7));
+        assert_eq!(cpu.registers[0..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert!(!cpu.has_exited());
+        execute!(Exit());
+        assert!(cpu.has_exited());
+    }
+
+    #[test]
+    fn xo_chip() {
+        let mut ram = Ram::with_size(Platform::XoChip.memory_size());
+        let mut display = Display::new(HeadlessBackend::new());
+        let mut input = HeadlessBackend::new();
+        let mut cpu = CPU::with_quirks(Platform::XoChip.quirks());
+
+        macro_rules! execute {
+            ($instruction:expr) => {
+                cpu.execute($instruction, &mut ram.memory, &mut display, &mut input)
+                    .unwrap();
+            };
+        }
+
+        assert_eq!(CPU::decode(0xF000), SetIndexLong());
+        assert_eq!(CPU::decode(0xF201), SelectPlanes(2));
+        assert_eq!(CPU::decode(0x5122), StoreRange(1, 2));
+        assert_eq!(CPU::decode(0x5123), LoadRange(1, 2));
+        assert_eq!(CPU::decode(0x5121), Unknown(0x5121));
+        assert_eq!(
+            disassemble(&[0xF0, 0x00, 0xE0, 0x00, 0xF3, 0x3A]),
+            "LD I, 0xE000\nPITCH V3\n"
         );
+
+        ram.memory[0x200..0x206].copy_from_slice(&[0xF0, 0x00, 0xE0, 0x00, 0x00, 0xE0]);
+        cpu.pc = 0x200;
+        let instruction = CPU::decode(cpu.fetch(&ram.memory).unwrap());
+        execute!(instruction);
+        assert_eq!((cpu.i, cpu.pc), (0xE000, 0x204));
+
+        cpu.pc = 0x200;
+        execute!(SkipEq(0x0, 0x0));
+        assert_eq!(cpu.pc, 0x204);
+        execute!(SkipEq(0x0, 0x0));
+        assert_eq!(cpu.pc, 0x206);
+
+        for i in 0..4 {
+            execute!(Set(i, 0x10 + i));
+        }
+        execute!(StoreRange(3, 1));
+        assert_eq!(ram.memory[0xE000..0xE003], [0x13, 0x12, 0x11]);
+        assert_eq!(cpu.i, 0xE000);
+        execute!(LoadRange(0, 2));
+        assert_eq!(cpu.registers[0..4], [0x13, 0x12, 0x11, 0x13]);
+
+        ram.memory[0xE000..0xE010].copy_from_slice(&[0xAA; 16]);
+        execute!(LoadAudioPattern());
+        assert_eq!(cpu.audio_pattern(), [0xAA; 16]);
+        execute!(Set(0x5, 96));
+        execute!(SetPitch(0x5));
+        assert_eq!(cpu.pitch(), 96);
+
+        ram.memory[0xE000..0xE002].copy_from_slice(&[0x80, 0xC0]);
+        execute!(Set(0x0, 0));
+        execute!(SelectPlanes(3));
+        execute!(super::Instruction::Display {
+            x: 0x0,
+            y: 0x0,
+            height: 1,
+        });
+        assert_eq!(display.pixels[0..2], [0b11, 0b10]);
+
+        execute!(SelectPlanes(2));
+        execute!(ScrollUp(0));
+        execute!(ScrollRight());
+        assert_eq!(display.pixels[0..6], [0b01, 0, 0, 0, 0b10, 0b10]);
+        execute!(ClearScreen());
+        assert_eq!(display.pixels[0..6], [0b01, 0, 0, 0, 0, 0]);
+
+        execute!(SetIndex(0xFFF));
+        execute!(Store(0xF));
+        assert_eq!(cpu.i, 0x100F);
     }
 }
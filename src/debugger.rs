@@ -0,0 +1,890 @@
+use crate::{
+    constant::debugger::{EXAMINE_BYTES_PER_LINE, EXAMINE_LENGTH},
+    cpu::{self, AccessKind, CPU},
+};
+use std::{collections::BTreeSet, ops::Range};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Register {
+    V(u8),
+    Index,
+    ProgramCounter,
+    DelayTimer,
+    SoundTimer,
+}
+
+impl Register {
+    fn parse(name: &str) -> Option<Self> {
+        return match name {
+            "i" => Some(Register::Index),
+            "pc" => Some(Register::ProgramCounter),
+            "dt" => Some(Register::DelayTimer),
+            "st" => Some(Register::SoundTimer),
+            _ => {
+                let x = name.strip_prefix('v')?;
+                match x.len() {
+                    1 => u8::from_str_radix(x, 16).ok().map(Register::V),
+                    _ => None,
+                }
+            }
+        };
+    }
+
+    fn name(self) -> String {
+        return match self {
+            Register::V(x) => format!("V{x:X}"),
+            Register::Index => "I".to_string(),
+            Register::ProgramCounter => "PC".to_string(),
+            Register::DelayTimer => "DT".to_string(),
+            Register::SoundTimer => "ST".to_string(),
+        };
+    }
+
+    fn get(self, cpu: &CPU) -> u16 {
+        return match self {
+            Register::V(x) => cpu.registers()[x as usize] as u16,
+            Register::Index => cpu.index(),
+            Register::ProgramCounter => cpu.pc,
+            Register::DelayTimer => cpu.delay_timer() as u16,
+            Register::SoundTimer => cpu.sound_timer() as u16,
+        };
+    }
+
+    fn set(self, cpu: &mut CPU, value: u16) -> Result<(), String> {
+        let byte =
+            || u8::try_from(value).map_err(|_| format!("{} only holds one byte", self.name()));
+
+        match self {
+            Register::V(x) => cpu.set_register(x as usize, byte()?),
+            Register::Index => cpu.set_index(value),
+            Register::ProgramCounter => cpu.pc = value,
+            Register::DelayTimer => cpu.set_delay_timer(byte()?),
+            Register::SoundTimer => cpu.set_sound_timer(byte()?),
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WatchKind {
+    Read,
+    Write,
+    // Either reads or writes
+    Access,
+}
+
+impl WatchKind {
+    fn name(self) -> &'static str {
+        return match self {
+            WatchKind::Read => "read",
+            WatchKind::Write => "write",
+            WatchKind::Access => "read or write",
+        };
+    }
+
+    fn matches(self, kind: AccessKind) -> bool {
+        return match self {
+            WatchKind::Read => kind == AccessKind::Read,
+            WatchKind::Write => kind == AccessKind::Write,
+            WatchKind::Access => true,
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Watch {
+    // Stops after an instruction accesses length bytes from the address
+    Memory {
+        address: u16,
+        length: u16,
+        kind: WatchKind,
+    },
+    // Stops after an instruction changes the register
+    Register(Register),
+}
+
+impl Watch {
+    fn name(self) -> String {
+        return match self {
+            Watch::Memory {
+                address, length: 1, ..
+            } => format!("0x{address:03X}"),
+            Watch::Memory {
+                address, length, ..
+            } => format!(
+                "0x{address:03X}-0x{:03X}",
+                address as usize + length as usize - 1
+            ),
+            Watch::Register(register) => register.name(),
+        };
+    }
+
+    fn range(self) -> Range<usize> {
+        return match self {
+            Watch::Memory {
+                address, length, ..
+            } => address as usize..address as usize + length as usize,
+            Watch::Register(_) => 0..0,
+        };
+    }
+
+    fn values(self, cpu: &CPU, memory: &[u8]) -> Vec<u16> {
+        return match self {
+            Watch::Memory { .. } => {
+                let range = self.range();
+                memory[range.start.min(memory.len())..range.end.min(memory.len())]
+                    .iter()
+                    .map(|&byte| byte as u16)
+                    .collect()
+            }
+            Watch::Register(register) => vec![register.get(cpu)],
+        };
+    }
+
+    // Whether both watch the same address or register
+    fn same_target(self, other: Watch) -> bool {
+        return match (self, other) {
+            (Watch::Memory { address: a, .. }, Watch::Memory { address: b, .. }) => a == b,
+            (Watch::Register(a), Watch::Register(b)) => a == b,
+            _ => false,
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Printable {
+    All,
+    Register(Register),
+    Stack,
+    Timers,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Command {
+    // Without an address the breakpoints are listed
+    Break(Option<u16>),
+    // Without an address every breakpoint is deleted
+    Delete(Option<u16>),
+    Step,
+    // Steps over a CALL by running until the subroutine returns
+    Next,
+    // Runs until the current subroutine returns
+    Finish,
+    Continue,
+    Print(Printable),
+    Examine { address: u16, length: usize },
+    Set(Register, u16),
+    SetMemory(u16, u8),
+    // Without a target the watchpoints are listed
+    Watch(Option<Watch>),
+    // Without a target every watchpoint is deleted
+    Unwatch(Option<Watch>),
+    Help,
+    Quit,
+}
+
+const HELP: &str = "\
+break [ADDR]          stop before the instruction at ADDR, or list breakpoints
+delete [ADDR]         delete the breakpoint at ADDR, or all of them
+watch [ADDR [LENGTH]] stop after memory is written, or list watchpoints
+watch REG             stop after a register changes
+rwatch ADDR [LENGTH]  stop after memory is read, instruction fetches included
+awatch ADDR [LENGTH]  stop after memory is read or written
+unwatch [ADDR|REG]    delete the watchpoint on ADDR or REG, or all of them
+step                  execute one instruction
+next                  execute one instruction, running CALLs to their RET
+finish                run until the current subroutine returns
+continue              run until a breakpoint or watchpoint
+print [WHAT]          show a register (v0-vf, i, pc, dt, st), stack, timers or all
+x ADDR [LENGTH]       show LENGTH bytes of memory from ADDR
+set REG VALUE         change a register
+set ADDR VALUE        change a byte of memory
+quit                  stop the machine
+help                  show this list
+Numbers are hexadecimal and an empty line repeats the last command. break,
+delete, step, next, finish, continue, print, watch, help and quit can be
+shortened to their first letter.";
+
+fn number(text: &str) -> Result<u16, String> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+
+    return u16::from_str_radix(digits, 16)
+        .map_err(|_| format!("'{text}' is not a hexadecimal number"));
+}
+
+fn watch(kind: WatchKind, target: &str, length: Option<&str>) -> Result<Watch, String> {
+    if let Some(register) = Register::parse(target) {
+        return match (kind, length) {
+            (WatchKind::Write, None) => Ok(Watch::Register(register)),
+            _ => Err("Registers are watched for changes with watch".to_string()),
+        };
+    }
+
+    let length = match length {
+        Some(length) => number(length)?,
+        None => 1,
+    };
+    if length == 0 {
+        return Err("Nothing to watch in 0 bytes".to_string());
+    }
+
+    return Ok(Watch::Memory {
+        address: number(target)?,
+        length,
+        kind,
+    });
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.to_ascii_lowercase();
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    return match words.as_slice() {
+        ["break" | "b"] => Ok(Command::Break(None)),
+        ["break" | "b", address] => Ok(Command::Break(Some(number(address)?))),
+        ["delete" | "d"] => Ok(Command::Delete(None)),
+        ["delete" | "d", address] => Ok(Command::Delete(Some(number(address)?))),
+        ["step" | "s"] => Ok(Command::Step),
+        ["next" | "n"] => Ok(Command::Next),
+        ["finish" | "f"] => Ok(Command::Finish),
+        ["continue" | "c"] => Ok(Command::Continue),
+        ["print" | "p"] | ["print" | "p", "all"] => Ok(Command::Print(Printable::All)),
+        ["print" | "p", "stack"] => Ok(Command::Print(Printable::Stack)),
+        ["print" | "p", "timers"] => Ok(Command::Print(Printable::Timers)),
+        ["print" | "p", name] => match Register::parse(name) {
+            Some(register) => Ok(Command::Print(Printable::Register(register))),
+            None => Err(format!("Unknown register '{name}'")),
+        },
+        ["x", address] => Ok(Command::Examine {
+            address: number(address)?,
+            length: EXAMINE_LENGTH,
+        }),
+        ["x", address, length] => Ok(Command::Examine {
+            address: number(address)?,
+            length: number(length)? as usize,
+        }),
+        ["set", target, value] => {
+            let value = number(value)?;
+            match Register::parse(target) {
+                Some(register) => Ok(Command::Set(register, value)),
+                None => match u8::try_from(value) {
+                    Ok(byte) => Ok(Command::SetMemory(number(target)?, byte)),
+                    Err(_) => Err("Memory only holds bytes".to_string()),
+                },
+            }
+        }
+        ["watch" | "w"] => Ok(Command::Watch(None)),
+        [
+            command @ ("watch" | "w" | "rwatch" | "awatch"),
+            target,
+            length @ ..,
+        ] if length.len() <= 1 => {
+            let kind = match *command {
+                "rwatch" => WatchKind::Read,
+                "awatch" => WatchKind::Access,
+                _ => WatchKind::Write,
+            };
+            Ok(Command::Watch(Some(watch(
+                kind,
+                target,
+                length.first().copied(),
+            )?)))
+        }
+        ["unwatch"] => Ok(Command::Unwatch(None)),
+        ["unwatch", target] => Ok(Command::Unwatch(Some(watch(
+            WatchKind::Write,
+            target,
+            None,
+        )?))),
+        ["help" | "h"] => Ok(Command::Help),
+        ["quit" | "q"] => Ok(Command::Quit),
+        [] => Err("No command".to_string()),
+        [command, ..] => Err(format!("Unknown command '{command}', try help")),
+    };
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum DebuggerAction {
+    None,
+    Output(String),
+    // Execution goes on until the debugger stops it again
+    Resume,
+    Quit,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum RunMode {
+    Step,
+    // Stops once the stack is back to the given depth
+    Next(usize),
+    // Stops once the stack is below the given depth
+    Finish(usize),
+    Continue,
+}
+
+struct Watchpoint {
+    watch: Watch,
+    // Values from before the current instruction
+    values: Vec<u16>,
+}
+
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+    // Address of the instruction being watched
+    watched_pc: Option<u16>,
+    // Why the machine stopped, when a watchpoint stopped it
+    report: Option<String>,
+    mode: RunMode,
+    last_command: Option<Command>,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debugger {
+    // Stops before the first instruction
+    pub fn new() -> Self {
+        return Debugger {
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            watched_pc: None,
+            report: None,
+            mode: RunMode::Step,
+            last_command: None,
+        };
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<u16> {
+        return &self.breakpoints;
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        return self.breakpoints.remove(&address);
+    }
+
+    pub fn watchpoints(&self) -> Vec<Watch> {
+        return self
+            .watchpoints
+            .iter()
+            .map(|watchpoint| watchpoint.watch)
+            .collect();
+    }
+
+    pub fn add_watchpoint(&mut self, watch: Watch) {
+        if !self.watchpoints().contains(&watch) {
+            self.watchpoints.push(Watchpoint {
+                watch,
+                values: Vec::new(),
+            });
+        }
+    }
+
+    // Removes the watchpoints on the same address or register
+    pub fn remove_watchpoint(&mut self, watch: Watch) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints
+            .retain(|watchpoint| !watchpoint.watch.same_target(watch));
+
+        return self.watchpoints.len() < count;
+    }
+
+    // Stops before the next instruction
+    pub fn interrupt(&mut self) {
+        self.mode = RunMode::Step;
+    }
+
+    // Lets the machine run freely, for when no more commands can be read
+    pub fn detach(&mut self) {
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+        self.mode = RunMode::Continue;
+    }
+
+    // Checked before every instruction
+    pub fn should_stop(&self, cpu: &CPU) -> bool {
+        if self.breakpoints.contains(&cpu.pc) {
+            return true;
+        }
+
+        return match self.mode {
+            RunMode::Step => true,
+            RunMode::Next(depth) => cpu.stack_depth() <= depth,
+            RunMode::Finish(depth) => cpu.stack_depth() < depth,
+            RunMode::Continue => false,
+        };
+    }
+
+    // Remembers the watched values before an instruction is executed
+    pub fn begin_instruction(&mut self, cpu: &CPU, memory: &[u8]) {
+        if self.watchpoints.is_empty() {
+            return;
+        }
+
+        for watchpoint in &mut self.watchpoints {
+            watchpoint.values = watchpoint.watch.values(cpu, memory);
+        }
+        self.watched_pc = Some(cpu.pc);
+    }
+
+    // Stops before the next instruction when the executed one triggered a
+    // watchpoint
+    pub fn end_instruction(&mut self, cpu: &CPU, memory: &[u8]) {
+        let Some(pc) = self.watched_pc.take() else {
+            return;
+        };
+        let instruction = cpu::disassemble_at(memory, pc as usize);
+
+        let mut reports = Vec::new();
+        for watchpoint in &self.watchpoints {
+            let watch = watchpoint.watch;
+            let values = watch.values(cpu, memory);
+            let range = watch.range();
+
+            let (event, change) = match watch {
+                Watch::Register(_) if values != watchpoint.values => (
+                    "changed",
+                    format!("0x{:X} -> 0x{:X}", watchpoint.values[0], values[0]),
+                ),
+                Watch::Register(_) => continue,
+                Watch::Memory { kind, .. } => {
+                    let accessed = |access_kind| {
+                        kind.matches(access_kind)
+                            && cpu.memory_accesses().iter().any(|access| {
+                                access.kind == access_kind
+                                    && access.range.start < range.end
+                                    && range.start < access.range.end
+                            })
+                    };
+                    if accessed(AccessKind::Write) {
+                        (
+                            "written",
+                            format!("{} -> {}", bytes(&watchpoint.values), bytes(&values)),
+                        )
+                    } else if accessed(AccessKind::Read) {
+                        ("read", bytes(&values))
+                    } else {
+                        continue;
+                    }
+                }
+            };
+
+            reports.push(format!(
+                "Watchpoint on {} {event} at 0x{pc:03X}: {instruction}\n{change}",
+                watch.name()
+            ));
+        }
+
+        if !reports.is_empty() {
+            self.report = Some(reports.join("\n"));
+            self.mode = RunMode::Step;
+        }
+    }
+
+    // Where the machine stopped, and why if a watchpoint stopped it
+    pub fn stop_message(&mut self, cpu: &CPU, memory: &[u8]) -> String {
+        let instruction = cpu::disassemble_at(memory, cpu.pc as usize);
+        let location = match self.breakpoints.contains(&cpu.pc) {
+            true => format!("Breakpoint at 0x{:03X}: {instruction}", cpu.pc),
+            false => format!("0x{:03X}: {instruction}", cpu.pc),
+        };
+
+        return match self.report.take() {
+            Some(report) => format!("{report}\n{location}"),
+            None => location,
+        };
+    }
+
+    // Runs a line typed at the prompt
+    pub fn execute(&mut self, line: &str, cpu: &mut CPU, memory: &mut [u8]) -> DebuggerAction {
+        let command = match (line.trim(), self.last_command) {
+            ("", Some(command)) => command,
+            ("", None) => return DebuggerAction::None,
+            (line, _) => match parse(line) {
+                Ok(command) => command,
+                Err(error) => return DebuggerAction::Output(error),
+            },
+        };
+        self.last_command = Some(command);
+
+        let output = match command {
+            Command::Break(Some(address)) => {
+                self.add_breakpoint(address);
+                format!("Breakpoint at 0x{address:03X}")
+            }
+            Command::Break(None) if self.breakpoints.is_empty() => "No breakpoints".to_string(),
+            Command::Break(None) => format!(
+                "Breakpoints: {}",
+                self.breakpoints
+                    .iter()
+                    .map(|address| format!("0x{address:03X}"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            Command::Delete(Some(address)) => match self.remove_breakpoint(address) {
+                true => format!("Deleted the breakpoint at 0x{address:03X}"),
+                false => format!("No breakpoint at 0x{address:03X}"),
+            },
+            Command::Delete(None) => {
+                self.breakpoints.clear();
+                "Deleted all breakpoints".to_string()
+            }
+            Command::Step => return self.resume(RunMode::Step),
+            Command::Next => return self.resume(RunMode::Next(cpu.stack_depth())),
+            Command::Finish if cpu.stack_depth() == 0 => "Not inside a subroutine".to_string(),
+            Command::Finish => return self.resume(RunMode::Finish(cpu.stack_depth())),
+            Command::Continue => return self.resume(RunMode::Continue),
+            Command::Print(printable) => print(printable, cpu),
+            Command::Examine { address, length } => examine(memory, address, length),
+            Command::Set(register, value) => match register.set(cpu, value) {
+                Ok(()) => print(Printable::Register(register), cpu),
+                Err(error) => error,
+            },
+            Command::SetMemory(address, value) => match memory.get_mut(address as usize) {
+                Some(byte) => {
+                    *byte = value;
+                    examine(memory, address, 1)
+                }
+                None => format!("0x{address:03X} is outside of memory"),
+            },
+            Command::Watch(Some(watch)) => {
+                self.add_watchpoint(watch);
+                format!("Watchpoint on {}", watch.name())
+            }
+            Command::Watch(None) if self.watchpoints.is_empty() => "No watchpoints".to_string(),
+            Command::Watch(None) => format!(
+                "Watchpoints: {}",
+                self.watchpoints
+                    .iter()
+                    .map(|watchpoint| match watchpoint.watch {
+                        Watch::Memory { kind, .. } => {
+                            format!("{} ({})", watchpoint.watch.name(), kind.name())
+                        }
+                        Watch::Register(_) => watchpoint.watch.name(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Command::Unwatch(Some(watch)) => match self.remove_watchpoint(watch) {
+                true => format!("Deleted the watchpoint on {}", watch.name()),
+                false => format!("No watchpoint on {}", watch.name()),
+            },
+            Command::Unwatch(None) => {
+                self.watchpoints.clear();
+                "Deleted all watchpoints".to_string()
+            }
+            Command::Help => HELP.to_string(),
+            Command::Quit => return DebuggerAction::Quit,
+        };
+
+        return DebuggerAction::Output(output);
+    }
+
+    fn resume(&mut self, mode: RunMode) -> DebuggerAction {
+        self.mode = mode;
+
+        return DebuggerAction::Resume;
+    }
+}
+
+fn print(printable: Printable, cpu: &CPU) -> String {
+    let hex = |values: &[u16]| {
+        return values
+            .iter()
+            .map(|value| format!("0x{value:03X}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+    };
+
+    return match printable {
+        Printable::All => {
+            let mut lines = vec![format!("PC 0x{:03X}  I 0x{:03X}", cpu.pc, cpu.index())];
+            for (row, values) in cpu.registers().chunks(8).enumerate() {
+                lines.push(
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| format!("V{:X} {value:02X}", row * 8 + i))
+                        .collect::<Vec<_>>()
+                        .join("  "),
+                );
+            }
+            lines.push(print(Printable::Timers, cpu));
+            lines.push(print(Printable::Stack, cpu));
+            lines.join("\n")
+        }
+        Printable::Register(register) => {
+            format!("{} = 0x{:X}", register.name(), register.get(cpu))
+        }
+        Printable::Stack if cpu.stack().is_empty() => "Stack is empty".to_string(),
+        Printable::Stack => format!("Stack: {}", hex(cpu.stack())),
+        Printable::Timers => format!("DT {:02X}  ST {:02X}", cpu.delay_timer(), cpu.sound_timer()),
+    };
+}
+
+fn bytes(values: &[u16]) -> String {
+    return values
+        .iter()
+        .map(|value| format!("{value:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+}
+
+fn examine(memory: &[u8], address: u16, length: usize) -> String {
+    let start = address as usize;
+    if start >= memory.len() {
+        return format!("0x{address:03X} is outside of memory");
+    }
+    let bytes = &memory[start..(start + length).min(memory.len())];
+
+    return bytes
+        .chunks(EXAMINE_BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, line)| {
+            let values: Vec<String> = line.iter().map(|byte| format!("{byte:02X}")).collect();
+            format!(
+                "0x{:03X}: {}",
+                start + i * EXAMINE_BYTES_PER_LINE,
+                values.join(" ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chip8::{CHIP8, ExecutionStatus},
+        cpu::ExecutionError,
+        display::HeadlessBackend,
+    };
+
+    #[test]
+    fn parse_commands() {
+        for command in [
+            "break", "delete", "step", "next", "finish", "continue", "print", "watch", "help",
+            "quit",
+        ] {
+            assert_eq!(parse(&command[..1]), parse(command));
+        }
+        assert!(parse("u").is_err());
+
+        assert_eq!(parse("b 2A0"), Ok(Command::Break(Some(0x2A0))));
+        assert_eq!(parse("break 0x2a0"), Ok(Command::Break(Some(0x2A0))));
+        assert_eq!(parse("  Delete  "), Ok(Command::Delete(None)));
+        assert_eq!(parse("n"), Ok(Command::Next));
+        assert_eq!(
+            parse("p vB"),
+            Ok(Command::Print(Printable::Register(Register::V(0xB))))
+        );
+        assert_eq!(parse("print stack"), Ok(Command::Print(Printable::Stack)));
+        assert_eq!(
+            parse("x 300 20"),
+            Ok(Command::Examine {
+                address: 0x300,
+                length: 0x20
+            })
+        );
+        assert_eq!(
+            parse("set i 0x3FF"),
+            Ok(Command::Set(Register::Index, 0x3FF))
+        );
+        assert_eq!(parse("set 300 ff"), Ok(Command::SetMemory(0x300, 0xFF)));
+
+        assert_eq!(
+            parse("awatch 300 3"),
+            Ok(Command::Watch(Some(Watch::Memory {
+                address: 0x300,
+                length: 3,
+                kind: WatchKind::Access
+            })))
+        );
+        assert_eq!(
+            parse("w i"),
+            Ok(Command::Watch(Some(Watch::Register(Register::Index))))
+        );
+
+        assert!(parse("set 300 100").is_err());
+        assert!(parse("rwatch v0").is_err());
+        assert!(parse("watch 300 0").is_err());
+        assert!(parse("print v10").is_err());
+        assert!(parse("break here").is_err());
+        assert!(parse("jump 200").is_err());
+    }
+
+    #[test]
+    fn machine_debugging() {
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        // 200: CALL 0x208; LD V1, 0x1; JP 0x206; JP 0x206
+        // 208: LD V2, 0x2; CALL 0x20E; RET
+        // 20E: LD V3, 0x3; RET
+        chip8
+            .load_rom(&[
+                0x22, 0x08, 0x61, 0x01, 0x12, 0x06, 0x12, 0x06, 0x62, 0x02, 0x22, 0x0E, 0x00, 0xEE,
+                0x63, 0x03, 0x00, 0xEE,
+            ])
+            .unwrap();
+        chip8.set_debug(true);
+        chip8.input_backend_mut().queue_commands(&[
+            "break 20e",
+            "s",
+            "",
+            "next",
+            "print stack",
+            "finish",
+            "finish",
+            "set v1 ff",
+            "set 300 ff",
+            "x 2fe 4",
+            "n",
+            "p v1",
+        ]);
+
+        assert_eq!(chip8.run_cycles(10), ExecutionStatus::Running);
+        let logs = chip8.display_backend().logs().to_vec();
+        assert_eq!(
+            logs,
+            [
+                "0x200: CALL 0x208",
+                "Breakpoint at 0x20E",
+                "0x208: LD V2, 0x2",
+                "0x20A: CALL 0x20E",
+                // next ran the call but the breakpoint stopped it
+                "Breakpoint at 0x20E: LD V3, 0x3",
+                "Stack: 0x202 0x20C",
+                "0x20C: RET",
+                "0x202: LD V1, 0x1",
+                "V1 = 0xFF",
+                "0x300: FF",
+                "0x2FE: 00 00 FF 00",
+                "0x204: JP 0x206",
+                "V1 = 0x1",
+            ]
+        );
+
+        // Without commands the debugger lets the machine run on
+        assert_eq!(chip8.debug_info().pc, 0x206);
+        assert_eq!(chip8.debug_info().registers[..4], [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn watchpoints() {
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        // LD I, 0x300; LD V0, 0x7; LD [I], V0; LD I, 0x2FF; LD V1, [I]; JP 0x20A
+        chip8
+            .load_rom(&[
+                0xA3, 0x00, 0x60, 0x07, 0xF0, 0x55, 0xA2, 0xFF, 0xF1, 0x65, 0x12, 0x0A,
+            ])
+            .unwrap();
+        chip8.set_debug(true);
+        chip8.input_backend_mut().queue_commands(&[
+            "watch 300",
+            "rwatch 2ff 2",
+            "watch v1",
+            "watch",
+            "c",
+            "c",
+            "unwatch v1",
+            // The fetch of the jump reads its own address
+            "rwatch 20a",
+            "c",
+            "unwatch",
+            "watch",
+        ]);
+
+        assert_eq!(chip8.run_cycles(10), ExecutionStatus::Running);
+        let logs = chip8.display_backend().logs().to_vec();
+        assert_eq!(
+            logs,
+            [
+                "0x200: LD I, 0x300",
+                "Watchpoint on 0x300",
+                "Watchpoint on 0x2FF-0x300",
+                "Watchpoint on V1",
+                "Watchpoints: 0x300 (write), 0x2FF-0x300 (read), V1",
+                "Watchpoint on 0x300 written at 0x204: LD [I], V0\n00 -> 07\n\
+                 0x206: LD I, 0x2FF",
+                "Watchpoint on 0x2FF-0x300 read at 0x208: LD V1, [I]\n00 07\n\
+                 Watchpoint on V1 changed at 0x208: LD V1, [I]\n0x0 -> 0x7\n\
+                 0x20A: JP 0x20A",
+                "Deleted the watchpoint on V1",
+                "Watchpoint on 0x20A",
+                "Watchpoint on 0x20A read at 0x20A: JP 0x20A\n12\n0x20A: JP 0x20A",
+                "Deleted all watchpoints",
+                "No watchpoints",
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_on_fault() {
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        // LD V0, 0x42; unknown 0x8008; JP 0x204
+        chip8
+            .load_rom(&[0x60, 0x42, 0x80, 0x08, 0x12, 0x04])
+            .unwrap();
+        chip8.set_debug(true);
+        chip8.input_backend_mut().queue_commands(&[
+            "c",
+            "p pc",
+            "p v0",
+            "x 202 2",
+            "set 202 70",
+            "s",
+            "s",
+            "p v0",
+            "q",
+        ]);
+
+        assert_eq!(chip8.run_cycles(10), ExecutionStatus::Stopped);
+        let logs = chip8.display_backend().logs().to_vec();
+        assert_eq!(logs[0], "0x200: LD V0, 0x42");
+        assert!(logs[1].starts_with("0x202: unknown instruction 0x8008\nPC: 0x202"));
+        assert_eq!(
+            logs[2..],
+            [
+                "PC = 0x202",
+                "V0 = 0x42",
+                "0x202: 80 08",
+                "0x202: 70",
+                // Patched, the faulting instruction runs again
+                "0x202: ADD V0, 0x8",
+                "0x204: JP 0x204",
+                "V0 = 0x4A",
+            ]
+        );
+
+        // Without commands the fault ends the run as it does without a debugger
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        chip8.load_rom(&[0x80, 0x08]).unwrap();
+        chip8.set_debug(true);
+        chip8.input_backend_mut().queue_commands(&["c"]);
+        let error = ExecutionError::UnknownInstruction {
+            pc: 0x200,
+            opcode: 0x8008,
+        };
+        assert_eq!(chip8.run_cycles(10), ExecutionStatus::Error(error));
+        assert_eq!(chip8.fault(), Some(error));
+    }
+
+    #[test]
+    fn quit_from_prompt() {
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        chip8.load_rom(&[0x12, 0x00]).unwrap();
+        chip8.set_debug(true);
+        chip8.input_backend_mut().queue_commands(&["help", "q"]);
+
+        assert_eq!(chip8.run_cycles(5), ExecutionStatus::Stopped);
+        assert!(chip8.display_backend().logs()[1].starts_with("break [ADDR]"));
+    }
+}
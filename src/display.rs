@@ -1,58 +1,350 @@
-use crate::constant::display::{
-    CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH, CLI_BACKEND_BUFFER_SIZE,
+use crate::{
+    browser::{self, MenuKey},
+    constant::display::{
+        CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH, CLI_BACKEND_BUFFER_SIZE,
+        SUPER_CHIP_DISPLAY_HEIGHT, SUPER_CHIP_DISPLAY_WIDTH,
+    },
+    graphics::GraphicsProtocol,
+    hud::{self, DebugInfo},
+    input::{Hotkey, InputBackend},
+    palette::{Palette, TerminalColors},
+    state::{StateError, StateReader, StateWriter},
 };
 use crossterm::{
-    event::{self, Event, poll},
+    event::{self, Event, KeyCode, KeyModifiers, poll},
     terminal,
 };
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
 use std::{
-    collections::HashSet,
-    io::{self, Read, Write, stdin},
+    cell::RefCell,
+    collections::{HashSet, VecDeque},
+    io::{self, Write},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
     time::{Duration, Instant},
 };
 
 pub trait DisplayBackend: Default {
-    fn render(&mut self, pixels: &[[bool; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT]);
-    fn read_keys(&mut self) -> Vec<u8>;
-    fn wait_for_key(&mut self) -> u8;
-    fn log(&self, message: String);
+    // Each pixel holds the bits of the bitplanes it is lit on
+    fn render(&mut self, pixels: &[u8], width: usize, height: usize);
+    fn log(&mut self, message: String);
+
+    // True while the backend animates an unchanged frame, e.g. fading pixels
+    fn needs_redraw(&self) -> bool {
+        return false;
+    }
+
+    // Colours the backend draws with, if it has a palette
+    fn palette(&self) -> Option<Palette> {
+        return None;
+    }
+
+    // Machine state is only gathered while the backend shows it
+    fn wants_debug_info(&self) -> bool {
+        return false;
+    }
+
+    fn set_debug_info(&mut self, _info: DebugInfo) {}
+
+    // Shows a list to pick from instead of the display
+    fn render_menu(&mut self, _title: &str, _lines: &[String], _selected: usize) {}
+
+    // Whether render_menu draws anything for a menu to be usable
+    fn shows_menus(&self) -> bool {
+        return false;
+    }
+
+    // Called once per emulated 60 Hz frame with the framebuffer at its end,
+    // for effects that advance with time instead of with rendering
+    fn frame_tick(&mut self, _pixels: &[u8]) {}
+}
+
+// Lets one backend act as both the display and the input of a machine
+pub type SharedBackend<B> = Rc<RefCell<B>>;
+
+impl<B: DisplayBackend> DisplayBackend for SharedBackend<B> {
+    fn render(&mut self, pixels: &[u8], width: usize, height: usize) {
+        self.borrow_mut().render(pixels, width, height);
+    }
+
+    fn log(&mut self, message: String) {
+        self.borrow_mut().log(message);
+    }
+
+    fn needs_redraw(&self) -> bool {
+        return self.borrow().needs_redraw();
+    }
+
+    fn palette(&self) -> Option<Palette> {
+        return self.borrow().palette();
+    }
+
+    fn wants_debug_info(&self) -> bool {
+        return self.borrow().wants_debug_info();
+    }
+
+    fn set_debug_info(&mut self, info: DebugInfo) {
+        self.borrow_mut().set_debug_info(info);
+    }
+
+    fn render_menu(&mut self, title: &str, lines: &[String], selected: usize) {
+        self.borrow_mut().render_menu(title, lines, selected);
+    }
+
+    fn shows_menus(&self) -> bool {
+        return self.borrow().shows_menus();
+    }
+
+    fn frame_tick(&mut self, pixels: &[u8]) {
+        self.borrow_mut().frame_tick(pixels);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum CLIRenderMode {
+    // One character per pixel
+    #[default]
+    Characters,
+    // Two rows of pixels per character cell using ▀, ▄ and █
+    HalfBlock,
+    // A 2x4 block of pixels per character cell using Braille dots
+    Braille,
+    // A scaled bitmap instead of characters
+    Graphics(GraphicsProtocol),
+}
+
+// A character on the terminal with its foreground and background colours
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Cell {
+    character: char,
+    colors: Option<(u32, u32)>,
 }
 
 pub struct CLIBackend {
     pub pixel_character: char,
+    pub render_mode: CLIRenderMode,
+    // Without a palette the terminal's own colours are used
+    pub palette: Option<Palette>,
+    pub terminal_colors: TerminalColors,
     buffer: String,
+    // What the terminal currently shows, to only rewrite changed cells
+    cells: Vec<Cell>,
+    columns: usize,
+    rows: usize,
     key_map: [char; 16],
+    pending_keys: Vec<u8>,
+    pending_hotkeys: Vec<Hotkey>,
 }
 
 impl Drop for CLIBackend {
     fn drop(&mut self) {
+        if let CLIRenderMode::Graphics(protocol) = self.render_mode {
+            print!("{}", protocol.clear());
+        }
+        print!("{esc}[?25h", esc = 27 as char);
+        io::stdout().flush().unwrap();
         terminal::disable_raw_mode().unwrap();
     }
 }
 
 impl CLIBackend {
     pub fn new() -> Self {
-        terminal::enable_raw_mode().unwrap();
+        return Self::try_new().unwrap();
+    }
 
-        return CLIBackend {
+    pub fn try_new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+
+        return Ok(CLIBackend {
             pixel_character: 'O',
-            // ToDo: Replace this with array
+            render_mode: CLIRenderMode::default(),
+            palette: None,
+            terminal_colors: TerminalColors::detect(),
             buffer: String::with_capacity(CLI_BACKEND_BUFFER_SIZE),
+            cells: Vec::new(),
+            columns: 0,
+            rows: 0,
             // ToDo: Check the performance of enum for the key_map
             key_map: [
                 '1', '2', '3', '4', 'q', 'w', 'e', 'r', 'a', 's', 'd', 'f', 'z', 'x', 'c', 'v',
             ],
+            pending_keys: Vec::new(),
+            pending_hotkeys: Vec::new(),
+        });
+    }
+
+    // Terminals only report key presses, so holding the rewind key relies
+    // on the terminal's key repeat
+    fn hotkey(key_code: KeyCode) -> Option<Hotkey> {
+        return match key_code {
+            KeyCode::F(5) => Some(Hotkey::SaveState),
+            KeyCode::F(8) => Some(Hotkey::Break),
+            KeyCode::F(9) => Some(Hotkey::LoadState),
+            KeyCode::F(10) => Some(Hotkey::Record),
+            KeyCode::F(12) => Some(Hotkey::Screenshot),
+            KeyCode::Backspace => Some(Hotkey::Rewind),
+            KeyCode::Esc => Some(Hotkey::Browse),
+            _ => None,
         };
     }
 
-    fn clear() {
-        if cfg!(unix) {
-            print!("{esc}c", esc = 27 as char);
-            io::stdout().flush().unwrap();
-        } else if cfg!(windows) {
-            todo!();
+    fn key_index(&self, character: Option<char>) -> Option<u8> {
+        let character = character?;
+
+        return self
+            .key_map
+            .iter()
+            .position(|key_code| *key_code == character)
+            .map(|i| i as u8);
+    }
+
+    fn pixel_cell(&self, pixel: u8) -> char {
+        return match pixel {
+            0 => ' ',
+            1 => self.pixel_character,
+            2 => '+',
+            _ => '#',
+        };
+    }
+
+    fn half_block_cell(top: u8, bottom: u8) -> char {
+        return match (top != 0, bottom != 0) {
+            (false, false) => ' ',
+            (true, false) => '▀',
+            (false, true) => '▄',
+            (true, true) => '█',
+        };
+    }
+
+    fn character_cells(&self, pixels: &[u8]) -> Vec<Cell> {
+        return pixels
+            .iter()
+            .map(|&pixel| Cell {
+                character: self.pixel_cell(pixel),
+                colors: self
+                    .palette
+                    .map(|palette| (palette.color(pixel), palette.background())),
+            })
+            .collect();
+    }
+
+    // With a palette every cell is ▀ coloured by its top and bottom pixel
+    fn half_block_cells(pixels: &[u8], width: usize, palette: Option<Palette>) -> Vec<Cell> {
+        return pixels
+            .chunks(width * 2)
+            .flat_map(|rows| {
+                let (top, bottom) = rows.split_at(width.min(rows.len()));
+                (0..width).map(move |x| {
+                    let bottom = bottom.get(x).copied().unwrap_or(0);
+                    match palette {
+                        Some(palette) => Cell {
+                            character: '▀',
+                            colors: Some((palette.color(top[x]), palette.color(bottom))),
+                        },
+                        None => Cell {
+                            character: Self::half_block_cell(top[x], bottom),
+                            colors: None,
+                        },
+                    }
+                })
+            })
+            .collect();
+    }
+
+    // The dots of a cell take the colour of its brightest pixel
+    fn braille_cells(pixels: &[u8], width: usize, palette: Option<Palette>) -> Vec<Cell> {
+        // Bit of each dot in a Braille character, indexed by [y][x]
+        const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+        let columns = width.div_ceil(2);
+        let mut cells = Vec::new();
+        for rows in pixels.chunks(width * 4) {
+            for column in 0..columns {
+                let mut dots = 0;
+                let mut brightest = 1;
+                for (y, row) in rows.chunks_exact(width).enumerate() {
+                    for (x, &pixel) in row.iter().skip(column * 2).take(2).enumerate() {
+                        if pixel != 0 {
+                            dots |= DOTS[y][x];
+                            brightest = brightest.max(pixel);
+                        }
+                    }
+                }
+
+                cells.push(Cell {
+                    character: char::from_u32(0x2800 + dots).unwrap(),
+                    colors: palette.map(|palette| (palette.color(brightest), palette.background())),
+                });
+            }
         }
+
+        return cells;
+    }
+
+    // Returns the character cells of the frame with their column count, or
+    // None when the frame is drawn as an image
+    fn frame_cells(&self, pixels: &[u8], width: usize) -> Option<(Vec<Cell>, usize)> {
+        return match self.render_mode {
+            CLIRenderMode::Characters => Some((self.character_cells(pixels), width)),
+            CLIRenderMode::HalfBlock => {
+                Some((Self::half_block_cells(pixels, width, self.palette), width))
+            }
+            CLIRenderMode::Braille => Some((
+                Self::braille_cells(pixels, width, self.palette),
+                width.div_ceil(2),
+            )),
+            CLIRenderMode::Graphics(_) => None,
+        };
+    }
+
+    // Draws the frame at the largest whole multiple that fits the terminal
+    // while leaving a line for messages
+    fn render_image(
+        &mut self,
+        protocol: GraphicsProtocol,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+    ) {
+        let esc = 27 as char;
+
+        let (columns, rows) = terminal::size().unwrap_or((80, 24));
+        let (columns, rows) = (columns.max(1) as usize, rows.max(2) as usize);
+        // Terminals that do not report their size in pixels get 10x20 cells
+        let (cell_width, cell_height) = match terminal::window_size() {
+            Ok(size) if size.width > 0 && size.height > 0 => (
+                (size.width as usize / columns).max(1),
+                (size.height as usize / rows).max(1),
+            ),
+            _ => (10, 20),
+        };
+
+        let scale = (columns * cell_width / width)
+            .min((rows - 1) * cell_height / height)
+            .max(1);
+        let image_columns = (width * scale).div_ceil(cell_width);
+        let image_rows = (height * scale).div_ceil(cell_height);
+
+        self.buffer.clear();
+        // A smaller image would leave parts of the previous one behind
+        if image_columns != self.columns || image_rows != self.rows {
+            self.buffer.push_str(&format!("{esc}[?25l{esc}[2J"));
+        }
+        self.buffer.push_str(&format!("{esc}[H"));
+        self.buffer.push_str(&protocol.encode(
+            pixels,
+            width,
+            height,
+            scale,
+            &self.palette.unwrap_or_default(),
+        ));
+
+        self.columns = image_columns;
+        self.rows = image_rows;
+
+        print!("{}", self.buffer);
+        io::stdout().flush().unwrap();
     }
 }
 
@@ -62,43 +354,153 @@ impl Default for CLIBackend {
     }
 }
 
+// Moves the cursor to and rewrites only the cells that differ from the
+// previous frame. Without a previous frame the screen is cleared first.
+fn write_cell_updates(
+    buffer: &mut String,
+    previous: Option<&[Cell]>,
+    cells: &[Cell],
+    columns: usize,
+    terminal_colors: TerminalColors,
+) {
+    let esc = 27 as char;
+
+    if previous.is_none() {
+        buffer.push_str(&format!("{esc}[?25l{esc}[2J"));
+    }
+
+    let mut colors = None;
+    for (row, line) in cells.chunks_exact(columns).enumerate() {
+        let mut cursor = None;
+
+        for (column, &cell) in line.iter().enumerate() {
+            if previous.is_some_and(|previous| previous[row * columns + column] == cell) {
+                continue;
+            }
+
+            if cursor != Some(column) {
+                buffer.push_str(&format!("{esc}[{};{}H", row + 1, column + 1));
+            }
+            if cell.colors != colors {
+                match cell.colors {
+                    Some((foreground, background)) => {
+                        buffer.push_str(&terminal_colors.escape(foreground, background))
+                    }
+                    None => buffer.push_str(&format!("{esc}[0m")),
+                }
+                colors = cell.colors;
+            }
+            buffer.push(cell.character);
+            cursor = Some(column + 1);
+        }
+    }
+
+    if colors.is_some() {
+        buffer.push_str(&format!("{esc}[0m"));
+    }
+}
+
 impl DisplayBackend for CLIBackend {
-    fn render(&mut self, pixels: &[[bool; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT]) {
+    fn render(&mut self, pixels: &[u8], width: usize, height: usize) {
+        let Some((cells, columns)) = self.frame_cells(pixels, width) else {
+            if let CLIRenderMode::Graphics(protocol) = self.render_mode {
+                self.render_image(protocol, pixels, width, height);
+            }
+            return;
+        };
+        let rows = cells.len() / columns;
+
+        // Redraw everything when the layout changes
+        let previous = (columns == self.columns && rows == self.rows).then_some(&self.cells[..]);
+
         self.buffer.clear();
+        write_cell_updates(
+            &mut self.buffer,
+            previous,
+            &cells,
+            columns,
+            self.terminal_colors,
+        );
+
+        self.cells = cells;
+        self.columns = columns;
+        self.rows = rows;
+
+        print!("{}", self.buffer);
+        io::stdout().flush().unwrap();
+    }
+
+    fn log(&mut self, message: String) {
+        // Messages go below the frame, replacing the previous one
+        let message = message.replace("\n", "\r\n");
+        print!(
+            "{esc}[{};1H{esc}[J{}\r",
+            self.rows + 1,
+            message,
+            esc = 27 as char
+        );
+
+        io::stdout().flush().unwrap();
+    }
+
+    fn palette(&self) -> Option<Palette> {
+        return self.palette;
+    }
+
+    fn shows_menus(&self) -> bool {
+        return true;
+    }
+
+    fn render_menu(&mut self, title: &str, lines: &[String], selected: usize) {
+        let esc = 27 as char;
+        // Pseudo terminals without a size report 0 rows
+        let rows = match terminal::size() {
+            Ok((_, rows)) if rows > 0 => rows as usize,
+            _ => 24,
+        };
 
-        for row in pixels.iter() {
-            for &pixel in row {
-                self.buffer
-                    .push(if pixel { self.pixel_character } else { ' ' });
+        self.buffer.clear();
+        if let CLIRenderMode::Graphics(protocol) = self.render_mode {
+            self.buffer.push_str(&protocol.clear());
+        }
+        self.buffer
+            .push_str(&format!("{esc}[?25l{esc}[2J{esc}[H{title}\r\n\r\n"));
+        for i in browser::visible_range(lines.len(), selected, rows.saturating_sub(3)) {
+            match i == selected {
+                true => self
+                    .buffer
+                    .push_str(&format!("{esc}[7m{}{esc}[0m\r\n", lines[i])),
+                false => self.buffer.push_str(&format!("{}\r\n", lines[i])),
             }
-            self.buffer.push('\r');
-            self.buffer.push('\n');
         }
 
-        Self::clear();
+        // The next frame redraws the whole screen
+        self.cells.clear();
+        self.columns = 0;
+        self.rows = 0;
+
         print!("{}", self.buffer);
         io::stdout().flush().unwrap();
     }
+}
 
+impl InputBackend for CLIBackend {
     fn read_keys(&mut self) -> Vec<u8> {
-        let mut pressed_keys = HashSet::<u8>::new();
+        let mut pressed_keys = HashSet::<u8>::from_iter(self.pending_keys.drain(..));
 
         let start = Instant::now();
         let time_window = Duration::from_micros(10);
         let single_polling_time = Duration::from_micros(1);
 
         while start.elapsed() < time_window {
-            if poll(single_polling_time).unwrap() {
-                if let Event::Key(event) = event::read().unwrap() {
-                    if event.is_press() {
-                        if let Some(key_code) = self
-                            .key_map
-                            .iter()
-                            .position(|key_code| *key_code == event.code.as_char().unwrap())
-                        {
-                            pressed_keys.insert(key_code as u8);
-                        };
-                    }
+            if poll(single_polling_time).unwrap()
+                && let Event::Key(event) = event::read().unwrap()
+                && event.is_press()
+            {
+                if let Some(key_code) = self.key_index(event.code.as_char()) {
+                    pressed_keys.insert(key_code);
+                } else if let Some(hotkey) = Self::hotkey(event.code) {
+                    self.pending_hotkeys.push(hotkey);
                 }
             }
         }
@@ -108,28 +510,198 @@ impl DisplayBackend for CLIBackend {
         return pressed_keys;
     }
 
-    fn wait_for_key(&mut self) -> u8 {
+    fn wait_for_key(&mut self) -> Option<u8> {
+        if !self.pending_keys.is_empty() {
+            return Some(self.pending_keys.remove(0));
+        }
+
         loop {
-            for b in stdin().bytes() {
-                let b = b.unwrap() as char;
+            if let Event::Key(event) = event::read().unwrap()
+                && event.is_press()
+                && let Some(key_code) = self.key_index(event.code.as_char())
+            {
+                return Some(key_code);
+            }
+        }
+    }
 
-                if let Some(i) = self.key_map.iter().position(|k| b == *k) {
-                    return i as u8;
+    fn hotkeys(&mut self) -> Vec<Hotkey> {
+        while poll(Duration::ZERO).unwrap() {
+            // Keypad presses are kept for the next read_keys call
+            if let Event::Key(event) = event::read().unwrap()
+                && event.is_press()
+            {
+                if let Some(key_code) = self.key_index(event.code.as_char()) {
+                    self.pending_keys.push(key_code);
+                } else if let Some(hotkey) = Self::hotkey(event.code) {
+                    self.pending_hotkeys.push(hotkey);
                 }
             }
         }
+
+        return std::mem::take(&mut self.pending_hotkeys);
     }
 
-    fn log(&self, message: String) {
-        // ToDo: Get rid of redrawing
-        Self::clear();
+    fn menu_keys(&mut self) -> Vec<MenuKey> {
+        let mut keys = Vec::new();
 
-        print!("{}", self.buffer);
+        while poll(Duration::ZERO).unwrap() {
+            if let Event::Key(event) = event::read().unwrap()
+                && event.is_press()
+            {
+                keys.extend(match event.code {
+                    KeyCode::Up => Some(MenuKey::Up),
+                    KeyCode::Down => Some(MenuKey::Down),
+                    KeyCode::Enter => Some(MenuKey::Select),
+                    KeyCode::Esc => Some(MenuKey::Back),
+                    _ => None,
+                });
+            }
+        }
 
-        let message = message.replace("\n", "\r\n");
-        print!("{}\r", message);
+        return keys;
+    }
 
+    fn read_command(&mut self, prompt: &str) -> Option<String> {
+        let esc = 27 as char;
+        // The prompt goes on the line after the last message
+        print!("\r\n{prompt}{esc}[?25h");
         io::stdout().flush().unwrap();
+
+        // Raw mode leaves echoing and editing the line to us
+        let mut line = String::new();
+        let command = loop {
+            let Event::Key(event) = event::read().unwrap() else {
+                continue;
+            };
+            if !event.is_press() {
+                continue;
+            }
+
+            match event.code {
+                KeyCode::Enter => break Some(line),
+                KeyCode::Char('c' | 'd') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break None;
+                }
+                KeyCode::Char(character) => {
+                    line.push(character);
+                    print!("{character}");
+                }
+                KeyCode::Backspace if line.pop().is_some() => print!("\x08 \x08"),
+                _ => {}
+            }
+            io::stdout().flush().unwrap();
+        };
+
+        print!("{esc}[?25l");
+        io::stdout().flush().unwrap();
+
+        return command;
+    }
+}
+
+// Keeps everything in memory, for tests and for running without a terminal
+// or a window
+pub struct HeadlessBackend {
+    frame: Vec<u8>,
+    width: usize,
+    height: usize,
+    frames_rendered: usize,
+    key_queue: VecDeque<Vec<u8>>,
+    command_queue: VecDeque<String>,
+    logs: Vec<String>,
+}
+
+impl Default for HeadlessBackend {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl HeadlessBackend {
+    pub fn new() -> Self {
+        return HeadlessBackend {
+            frame: vec![0; CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT],
+            width: CHIP8_DISPLAY_WIDTH,
+            height: CHIP8_DISPLAY_HEIGHT,
+            frames_rendered: 0,
+            key_queue: VecDeque::new(),
+            command_queue: VecDeque::new(),
+            logs: Vec::new(),
+        };
+    }
+
+    // Queues the keys reported by one read_keys call
+    pub fn queue_keys(&mut self, keys: &[u8]) {
+        self.key_queue.push_back(keys.to_vec());
+    }
+
+    pub fn queued_keys(&self) -> usize {
+        return self.key_queue.len();
+    }
+
+    // Queues lines for the debugger prompt
+    pub fn queue_commands(&mut self, commands: &[&str]) {
+        self.command_queue
+            .extend(commands.iter().map(|command| command.to_string()));
+    }
+
+    pub fn frame(&self) -> &[u8] {
+        return &self.frame;
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn frames_rendered(&self) -> usize {
+        return self.frames_rendered;
+    }
+
+    pub fn logs(&self) -> &[String] {
+        return &self.logs;
+    }
+
+    pub fn take_logs(&mut self) -> Vec<String> {
+        return std::mem::take(&mut self.logs);
+    }
+}
+
+impl DisplayBackend for HeadlessBackend {
+    fn render(&mut self, pixels: &[u8], width: usize, height: usize) {
+        self.frame.clear();
+        self.frame.extend_from_slice(pixels);
+        self.width = width;
+        self.height = height;
+        self.frames_rendered += 1;
+    }
+
+    fn log(&mut self, message: String) {
+        self.logs.push(message);
+    }
+}
+
+impl InputBackend for HeadlessBackend {
+    fn read_keys(&mut self) -> Vec<u8> {
+        return self.key_queue.pop_front().unwrap_or_default();
+    }
+
+    fn wait_for_key(&mut self) -> Option<u8> {
+        while let Some(keys) = self.key_queue.pop_front() {
+            if let Some(&key) = keys.first() {
+                return Some(key);
+            }
+        }
+
+        return None;
+    }
+
+    fn read_command(&mut self, _prompt: &str) -> Option<String> {
+        return self.command_queue.pop_front();
     }
 }
 
@@ -138,29 +710,222 @@ pub struct WindowSize {
     pub height: usize,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum GUIScaleMode {
+    // Whole multiples only so every pixel has the same size
+    #[default]
+    Integer,
+    // Fills as much of the window as the aspect ratio allows
+    Fit,
+}
+
+// Area of the window the display is drawn in, the rest is letterboxed
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Viewport {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl GUIScaleMode {
+    fn viewport(
+        self,
+        window_width: usize,
+        window_height: usize,
+        pixels_width: usize,
+        pixels_height: usize,
+    ) -> Viewport {
+        let multiplier = (window_width / pixels_width).min(window_height / pixels_height);
+
+        // A window smaller than the display is always fitted
+        let (width, height) = if self == GUIScaleMode::Integer && multiplier > 0 {
+            (pixels_width * multiplier, pixels_height * multiplier)
+        } else if window_width * pixels_height <= window_height * pixels_width {
+            (window_width, window_width * pixels_height / pixels_width)
+        } else {
+            (window_height * pixels_width / pixels_height, window_height)
+        };
+
+        return Viewport {
+            x: (window_width - width) / 2,
+            y: (window_height - height) / 2,
+            width,
+            height,
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct GUIEffects {
+    // Darkens the bottom row of every pixel
+    pub scanlines: bool,
+    // Darkens the bottom row and right column of every pixel
+    pub pixel_grid: bool,
+}
+
+// Scales colors into a window sized buffer, filling the letterbox with black
+fn draw_scaled(
+    colors: &[u32],
+    pixels_width: usize,
+    pixels_height: usize,
+    buffer: &mut [u32],
+    window_width: usize,
+    viewport: Viewport,
+    effects: GUIEffects,
+) {
+    buffer.fill(0);
+
+    // Effects need at least two window pixels per pixel to leave anything lit
+    let scanlines =
+        (effects.scanlines || effects.pixel_grid) && viewport.height >= pixels_height * 2;
+    let grid = effects.pixel_grid && viewport.width >= pixels_width * 2;
+    let dim = |color: u32| (color >> 1) & 0x7F7F7F;
+
+    for y in 0..viewport.height {
+        let pixel_y = y * pixels_height / viewport.height;
+        let row_edge = scanlines && (y + 1) * pixels_height / viewport.height != pixel_y;
+        let row = &colors[pixel_y * pixels_width..][..pixels_width];
+        let start = (viewport.y + y) * window_width + viewport.x;
+
+        for (x, target) in buffer[start..start + viewport.width].iter_mut().enumerate() {
+            let pixel_x = x * pixels_width / viewport.width;
+            let column_edge = grid && (x + 1) * pixels_width / viewport.width != pixel_x;
+
+            *target = match row_edge || column_edge {
+                true => dim(row[pixel_x]),
+                false => row[pixel_x],
+            };
+        }
+    }
+}
+
+// Lets lit pixels fade out over a number of frames like a CRT phosphor,
+// which hides the flicker of sprites erased and redrawn with XOR
+pub struct PhosphorFilter {
+    frames: u32,
+    intensities: Vec<u32>,
+    colors: Vec<u32>,
+    // Whether the last tick changed how any pixel looks
+    fading: bool,
+}
+
+impl PhosphorFilter {
+    pub fn new(frames: u32) -> Self {
+        return PhosphorFilter {
+            frames: frames.max(1),
+            intensities: Vec::new(),
+            colors: Vec::new(),
+            fading: false,
+        };
+    }
+
+    pub fn frames(&self) -> u32 {
+        return self.frames;
+    }
+
+    pub fn is_fading(&self) -> bool {
+        return self.fading;
+    }
+
+    // Advances the fade by one 60 Hz frame, lit pixels going back to full
+    // intensity
+    pub fn tick(&mut self, pixels: &[u8], palette: &Palette) {
+        if self.intensities.len() != pixels.len() {
+            self.intensities = vec![0; pixels.len()];
+            self.colors = vec![palette.background(); pixels.len()];
+        }
+
+        self.fading = false;
+        for (&pixel, (intensity, color)) in pixels
+            .iter()
+            .zip(self.intensities.iter_mut().zip(self.colors.iter_mut()))
+        {
+            if pixel != 0 {
+                *intensity = self.frames;
+                *color = palette.color(pixel);
+            } else if *intensity > 0 {
+                *intensity -= 1;
+                self.fading = true;
+            }
+        }
+    }
+
+    // Returns the colour to show for each pixel, without advancing the fade
+    pub fn apply(&self, pixels: &[u8], palette: &Palette) -> Vec<u32> {
+        // Not ticked since the resolution changed
+        if self.intensities.len() != pixels.len() {
+            return pixels.iter().map(|&pixel| palette.color(pixel)).collect();
+        }
+
+        let background = palette.background();
+        return pixels
+            .iter()
+            .zip(self.intensities.iter().zip(self.colors.iter()))
+            .map(|(&pixel, (&intensity, &color))| match pixel {
+                0 => Self::blend(background, color, intensity, self.frames),
+                _ => palette.color(pixel),
+            })
+            .collect();
+    }
+
+    fn blend(background: u32, color: u32, intensity: u32, frames: u32) -> u32 {
+        let background = background.to_be_bytes();
+        let color = color.to_be_bytes();
+
+        let mut blended = [0; 4];
+        for channel in 1..4 {
+            let from = background[channel] as u32;
+            let to = color[channel] as u32;
+            blended[channel] = ((from * (frames - intensity) + to * intensity) / frames) as u8;
+        }
+
+        return u32::from_be_bytes(blended);
+    }
+}
+
 pub struct GUIBackend {
+    pub palette: Palette,
+    pub phosphor: Option<PhosphorFilter>,
+    pub scale_mode: GUIScaleMode,
+    pub effects: GUIEffects,
+    // Machine state drawn over the display, toggled with F1
+    pub show_debug_info: bool,
+    debug_info: Option<DebugInfo>,
+    debug_info_drawn: bool,
     window: Window,
+    borderless: bool,
     buffer: Vec<u32>,
+    // Window size the buffer was last drawn for
+    buffer_size: (usize, usize),
     key_map: [Key; 16],
+    hotkey_map: [(Key, Hotkey); 6],
+    held_hotkeys: Vec<Key>,
+    // Lines of the terminal the program was started from, read on a thread so
+    // the window stays responsive at the debugger prompt
+    commands: Option<Receiver<String>>,
 }
 
 impl GUIBackend {
     pub fn new(window_size: WindowSize) -> Self {
-        let mut window = Window::new(
-            "CHIP8",
-            window_size.width,
-            window_size.height,
-            WindowOptions::default(),
-        )
-        .unwrap();
-
-        window.set_target_fps(60);
+        return Self::try_new(window_size).unwrap();
+    }
 
-        let buffer = vec![0; window_size.width * window_size.height];
+    pub fn try_new(window_size: WindowSize) -> Result<Self, minifb::Error> {
+        let window = Self::open_window(window_size.width, window_size.height, false)?;
 
-        return GUIBackend {
+        return Ok(GUIBackend {
+            palette: Palette::default(),
+            phosphor: None,
+            scale_mode: GUIScaleMode::default(),
+            effects: GUIEffects::default(),
+            show_debug_info: false,
+            debug_info: None,
+            debug_info_drawn: false,
             window,
-            buffer,
+            borderless: false,
+            buffer: Vec::new(),
+            buffer_size: (0, 0),
             key_map: [
                 Key::Key1,
                 Key::Key2,
@@ -179,7 +944,58 @@ impl GUIBackend {
                 Key::C,
                 Key::V,
             ],
-        };
+            hotkey_map: [
+                (Key::F5, Hotkey::SaveState),
+                (Key::F8, Hotkey::Break),
+                (Key::F9, Hotkey::LoadState),
+                (Key::F10, Hotkey::Record),
+                (Key::F12, Hotkey::Screenshot),
+                (Key::Escape, Hotkey::Browse),
+            ],
+            held_hotkeys: Vec::new(),
+            commands: None,
+        });
+    }
+
+    fn open_window(width: usize, height: usize, borderless: bool) -> Result<Window, minifb::Error> {
+        let mut window = Window::new(
+            "CHIP8",
+            width,
+            height,
+            WindowOptions {
+                borderless,
+                title: !borderless,
+                resize: true,
+                topmost: borderless,
+                ..WindowOptions::default()
+            },
+        )?;
+
+        window.set_target_fps(60);
+
+        return Ok(window);
+    }
+
+    pub fn is_borderless(&self) -> bool {
+        return self.borderless;
+    }
+
+    // minifb cannot change the decorations of an open window, so it is
+    // reopened at the same size and position
+    pub fn set_borderless(&mut self, borderless: bool) -> Result<(), minifb::Error> {
+        if borderless == self.borderless {
+            return Ok(());
+        }
+
+        let (width, height) = self.window.get_size();
+        let (x, y) = self.window.get_position();
+        self.window = Self::open_window(width, height, borderless)?;
+        self.window.set_position(x, y);
+        self.borderless = borderless;
+        // The new window starts out empty
+        self.buffer_size = (0, 0);
+
+        return Ok(());
     }
 }
 
@@ -193,28 +1009,121 @@ impl Default for GUIBackend {
 }
 
 impl DisplayBackend for GUIBackend {
-    fn render(&mut self, pixels: &[[bool; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT]) {
+    fn render(&mut self, pixels: &[u8], pixels_width: usize, pixels_height: usize) {
         let (width, height) = self.window.get_size();
+        // Minimised
+        if width == 0 || height == 0 {
+            self.window.update();
+            return;
+        }
+
+        let colors = match &self.phosphor {
+            Some(phosphor) => phosphor.apply(pixels, &self.palette),
+            None => pixels
+                .iter()
+                .map(|&pixel| self.palette.color(pixel))
+                .collect(),
+        };
 
-        let height_multiplier = height / CHIP8_DISPLAY_HEIGHT;
-        let width_multiplier = width / CHIP8_DISPLAY_WIDTH;
-        for (i, row) in pixels.iter().enumerate() {
-            for (j, &pixel) in row.iter().enumerate() {
-                let value = pixel as u32 * 0x00FFFFFF;
+        self.buffer.resize(width * height, 0);
+        self.buffer_size = (width, height);
+        draw_scaled(
+            &colors,
+            pixels_width,
+            pixels_height,
+            &mut self.buffer,
+            width,
+            self.scale_mode
+                .viewport(width, height, pixels_width, pixels_height),
+            self.effects,
+        );
 
-                for x in i * height_multiplier..i * height_multiplier + height_multiplier {
-                    for y in j * width_multiplier..j * width_multiplier + width_multiplier {
-                        self.buffer[x * width + y] = value;
-                    }
-                }
-            }
+        // Drawn into the window buffer only, the framebuffer is left alone
+        self.debug_info_drawn = false;
+        if self.show_debug_info
+            && let Some(info) = &self.debug_info
+        {
+            hud::draw_text(&mut self.buffer, width, height, &info.lines(), None);
+            self.debug_info_drawn = true;
+        }
+
+        self.window
+            .update_with_buffer(&self.buffer, width, height)
+            .unwrap();
+    }
+
+    fn log(&mut self, message: String) {
+        println!("{message}");
+    }
+
+    fn needs_redraw(&self) -> bool {
+        if self.window.get_size() != self.buffer_size {
+            return true;
         }
+        // The overlay changes every frame and has to be removed when hidden
+        if self.show_debug_info || self.debug_info_drawn {
+            return true;
+        }
+
+        return self
+            .phosphor
+            .as_ref()
+            .is_some_and(|phosphor| phosphor.is_fading());
+    }
+
+    fn palette(&self) -> Option<Palette> {
+        return Some(self.palette);
+    }
+
+    fn wants_debug_info(&self) -> bool {
+        return self.show_debug_info;
+    }
+
+    fn set_debug_info(&mut self, info: DebugInfo) {
+        self.debug_info = Some(info);
+    }
+
+    fn frame_tick(&mut self, pixels: &[u8]) {
+        if let Some(phosphor) = &mut self.phosphor {
+            phosphor.tick(pixels, &self.palette);
+        }
+    }
+
+    fn shows_menus(&self) -> bool {
+        return true;
+    }
+
+    fn render_menu(&mut self, title: &str, lines: &[String], selected: usize) {
+        let (width, height) = self.window.get_size();
+        if width == 0 || height == 0 {
+            self.window.update();
+            return;
+        }
+
+        self.buffer.clear();
+        self.buffer.resize(width * height, 0);
+        // The next frame redraws the whole window
+        self.buffer_size = (0, 0);
+
+        let rows = hud::text_rows(width, height).saturating_sub(2);
+        let range = browser::visible_range(lines.len(), selected, rows);
+        let mut text = vec![title.to_string(), String::new()];
+        text.extend(lines[range.clone()].iter().cloned());
+        hud::draw_text(
+            &mut self.buffer,
+            width,
+            height,
+            &text,
+            Some(2 + selected - range.start),
+        );
 
         self.window
             .update_with_buffer(&self.buffer, width, height)
             .unwrap();
     }
+}
 
+impl InputBackend for GUIBackend {
     fn read_keys(&mut self) -> Vec<u8> {
         self.window.update();
 
@@ -231,49 +1140,531 @@ impl DisplayBackend for GUIBackend {
             .collect();
     }
 
-    fn wait_for_key(&mut self) -> u8 {
-        loop {
+    fn wait_for_key(&mut self) -> Option<u8> {
+        while self.window.is_open() {
             self.window.update();
 
             for pressed_key in self.window.get_keys() {
                 if let Some(i) = self.key_map.iter().position(|k| pressed_key == *k) {
-                    return i as u8;
+                    return Some(i as u8);
                 }
             }
         }
+
+        return None;
     }
 
-    fn log(&self, message: String) {
-        println!("{message}");
+    fn hotkeys(&mut self) -> Vec<Hotkey> {
+        self.window.update();
+
+        // read_keys updates the window many times per frame, so key presses
+        // are tracked here instead of relying on minifb's own repeat state
+        let held: Vec<Key> = self
+            .hotkey_map
+            .iter()
+            .map(|&(key, _)| key)
+            .filter(|&key| self.window.is_key_down(key))
+            .collect();
+
+        let mut hotkeys: Vec<Hotkey> = self
+            .hotkey_map
+            .iter()
+            .filter(|(key, _)| held.contains(key) && !self.held_hotkeys.contains(key))
+            .map(|&(_, hotkey)| hotkey)
+            .collect();
+        self.held_hotkeys = held;
+
+        if self.window.is_key_down(Key::Backspace) {
+            hotkeys.push(Hotkey::Rewind);
+        }
+
+        // Window toggles are handled here instead of by the machine
+        if self.window.is_key_pressed(Key::F1, KeyRepeat::No) {
+            self.show_debug_info = !self.show_debug_info;
+        }
+        if self.window.is_key_pressed(Key::F11, KeyRepeat::No) {
+            let borderless = !self.borderless;
+            if let Err(error) = self.set_borderless(borderless) {
+                self.log(format!("Could not change the window: {error}"));
+            }
+        }
+
+        return hotkeys;
+    }
+
+    fn menu_keys(&mut self) -> Vec<MenuKey> {
+        self.window.update();
+
+        return self
+            .window
+            .get_keys_pressed(KeyRepeat::Yes)
+            .iter()
+            .filter_map(|key| match key {
+                Key::Up => Some(MenuKey::Up),
+                Key::Down => Some(MenuKey::Down),
+                Key::Enter => Some(MenuKey::Select),
+                Key::Escape => Some(MenuKey::Back),
+                _ => None,
+            })
+            .collect();
+    }
+
+    fn read_command(&mut self, prompt: &str) -> Option<String> {
+        print!("{prompt}");
+        io::stdout().flush().unwrap();
+
+        let commands = self.commands.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                for line in io::stdin().lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+            receiver
+        });
+
+        while self.window.is_open() {
+            match commands.recv_timeout(Duration::from_millis(16)) {
+                Ok(command) => return Some(command),
+                Err(RecvTimeoutError::Timeout) => self.window.update(),
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+
+        return None;
     }
 }
 
 pub struct Display<B: DisplayBackend> {
-    pub pixels: [[bool; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT],
+    pub pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    planes: u8,
+    dirty: bool,
+    // Presents every change at once instead of once per frame, for debugging
+    pub present_on_draw: bool,
     pub backend: B,
 }
 
+// The display section of a save state, read before anything is restored
+pub(crate) struct DisplayState<'a> {
+    high_resolution: bool,
+    planes: u8,
+    pixels: &'a [u8],
+}
+
+impl<'a> DisplayState<'a> {
+    pub(crate) fn read_state(reader: &mut StateReader<'a>) -> Result<Self, StateError> {
+        let high_resolution = reader.read_bool()?;
+        let planes = reader.read_u8()?;
+        let pixels = reader.read_bytes()?;
+
+        let (width, height) = if high_resolution {
+            (SUPER_CHIP_DISPLAY_WIDTH, SUPER_CHIP_DISPLAY_HEIGHT)
+        } else {
+            (CHIP8_DISPLAY_WIDTH, CHIP8_DISPLAY_HEIGHT)
+        };
+        if pixels.len() != width * height || pixels.iter().any(|&pixel| pixel > 0b11) {
+            return Err(StateError::InvalidData("framebuffer"));
+        }
+        if planes > 0b11 {
+            return Err(StateError::InvalidData("plane selection"));
+        }
+
+        return Ok(DisplayState {
+            high_resolution,
+            planes,
+            pixels,
+        });
+    }
+}
+
 impl<B: DisplayBackend> Display<B> {
     pub fn new(backend: B) -> Self {
         return Display {
-            pixels: [[false; CHIP8_DISPLAY_WIDTH]; CHIP8_DISPLAY_HEIGHT],
+            pixels: vec![0; CHIP8_DISPLAY_WIDTH * CHIP8_DISPLAY_HEIGHT],
+            width: CHIP8_DISPLAY_WIDTH,
+            height: CHIP8_DISPLAY_HEIGHT,
+            planes: 0b01,
+            dirty: true,
+            present_on_draw: false,
             backend,
         };
     }
 
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn is_high_resolution(&self) -> bool {
+        return self.width == SUPER_CHIP_DISPLAY_WIDTH;
+    }
+
+    pub fn set_high_resolution(&mut self, high_resolution: bool) {
+        (self.width, self.height) = if high_resolution {
+            (SUPER_CHIP_DISPLAY_WIDTH, SUPER_CHIP_DISPLAY_HEIGHT)
+        } else {
+            (CHIP8_DISPLAY_WIDTH, CHIP8_DISPLAY_HEIGHT)
+        };
+
+        self.pixels = vec![0; self.width * self.height];
+        self.mark_dirty();
+    }
+
+    pub fn planes(&self) -> u8 {
+        return self.planes;
+    }
+
+    pub fn select_planes(&mut self, planes: u8) {
+        self.planes = planes & 0b11;
+    }
+
+    pub fn clear(&mut self) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel &= !self.planes;
+        }
+        self.mark_dirty();
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        return self.pixels[y * self.width + x];
+    }
+
+    // Returns true when a lit pixel of the plane was turned off. The caller
+    // marks the display dirty once the whole sprite is drawn.
+    pub fn toggle(&mut self, x: usize, y: usize, plane: u8) -> bool {
+        let pixel = &mut self.pixels[y * self.width + x];
+        *pixel ^= plane;
+
+        return *pixel & plane == 0;
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll(0, -(rows as isize));
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll(0, rows as isize);
+    }
+
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.scroll(columns as isize, 0);
+    }
+
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.scroll(-(columns as isize), 0);
+    }
+
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let source = self.pixels.clone();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let source_x = x as isize - dx;
+                let source_y = y as isize - dy;
+
+                let moved = if (0..self.width as isize).contains(&source_x)
+                    && (0..self.height as isize).contains(&source_y)
+                {
+                    source[source_y as usize * self.width + source_x as usize]
+                } else {
+                    0
+                };
+
+                let index = y * self.width + x;
+                self.pixels[index] = (source[index] & !self.planes) | (moved & self.planes);
+            }
+        }
+        self.mark_dirty();
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        return self.dirty;
+    }
+
+    // Back to a blank low resolution screen drawing on the first plane
+    pub fn reset(&mut self) {
+        self.planes = 0b01;
+        self.set_high_resolution(false);
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+
+        if self.present_on_draw {
+            self.present();
+        }
+    }
+
+    // Renders the framebuffer if it changed since it was last presented or
+    // the backend is still animating it
+    pub fn present(&mut self) {
+        if self.dirty || self.backend.needs_redraw() {
+            self.render();
+        }
+    }
+
     pub fn render(&mut self) {
-        self.backend.render(&self.pixels);
+        self.backend.render(&self.pixels, self.width, self.height);
+        self.dirty = false;
+    }
+
+    pub fn frame_tick(&mut self) {
+        self.backend.frame_tick(&self.pixels);
     }
 
-    pub fn read_keys(&mut self) -> Vec<u8> {
-        return self.backend.read_keys();
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.is_high_resolution());
+        writer.write_u8(self.planes);
+        writer.write_bytes(&self.pixels);
     }
 
-    pub fn wait_for_key(&mut self) -> u8 {
-        return self.backend.wait_for_key();
+    pub(crate) fn load_state(&mut self, state: DisplayState) {
+        self.set_high_resolution(state.high_resolution);
+        self.planes = state.planes;
+        self.pixels.copy_from_slice(state.pixels);
+        self.mark_dirty();
     }
 
-    pub fn log(&self, message: String) {
+    pub fn log(&mut self, message: String) {
         self.backend.log(message);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chip8::{CHIP8, ExecutionStatus},
+        palette::Theme,
+        quirks::Platform,
+    };
+
+    fn characters(cells: &[Cell]) -> String {
+        return cells.iter().map(|cell| cell.character).collect();
+    }
+
+    #[test]
+    fn headless_backend() {
+        let mut chip8 = CHIP8::new_custom_backends(HeadlessBackend::new(), HeadlessBackend::new());
+        // LD V0, K; LD F, V0; DRW V1, V1, 5; LD V0, K
+        chip8
+            .load_rom(&[0xF0, 0x0A, 0xF0, 0x29, 0xD1, 0x15, 0xF0, 0x0A])
+            .unwrap();
+        chip8.set_platform(Platform::Chip48);
+
+        chip8.input_backend_mut().queue_keys(&[]);
+        chip8.input_backend_mut().queue_keys(&[0x8, 0x9]);
+        assert_eq!(chip8.input_backend().queued_keys(), 2);
+
+        assert_eq!(chip8.run_cycles(2), ExecutionStatus::WaitingForKey);
+        assert_eq!(chip8.run_cycles(1), ExecutionStatus::WaitingForKey);
+        assert_eq!(chip8.run_cycles(3), ExecutionStatus::WaitingForKey);
+        assert_eq!(chip8.input_backend().queued_keys(), 0);
+
+        // Drawing only marks the display dirty, it is presented once per frame
+        assert_eq!(chip8.display_backend().frames_rendered(), 0);
+        assert_eq!(chip8.run_frame(), ExecutionStatus::WaitingForKey);
+        assert_eq!(chip8.run_frame(), ExecutionStatus::WaitingForKey);
+
+        let backend = chip8.display_backend();
+        assert_eq!(backend.frames_rendered(), 1);
+        assert_eq!(backend.width(), CHIP8_DISPLAY_WIDTH);
+        // Top row of the "8" glyph
+        assert_eq!(&backend.frame()[..4], &[1, 1, 1, 1]);
+        assert!(backend.logs().is_empty());
+    }
+
+    #[test]
+    fn present_once_per_frame() {
+        let mut display = Display::new(HeadlessBackend::new());
+        display.present();
+        display.present();
+        assert_eq!(display.backend.frames_rendered(), 1);
+
+        display.toggle(0, 0, 0b01);
+        display.clear();
+        display.toggle(1, 1, 0b01);
+        display.mark_dirty();
+        assert!(display.is_dirty());
+        assert_eq!(display.backend.frames_rendered(), 1);
+        display.present();
+        assert_eq!(display.backend.frames_rendered(), 2);
+        assert_eq!(display.backend.frame()[CHIP8_DISPLAY_WIDTH + 1], 1);
+
+        display.present_on_draw = true;
+        display.clear();
+        assert!(!display.is_dirty());
+        assert_eq!(display.backend.frames_rendered(), 3);
+        assert!(display.backend.frame().iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn half_block_cells() {
+        let mut pixels = vec![0; 4 * 4];
+        pixels[0] = 1;
+        pixels[4 + 1] = 2;
+        pixels[2] = 1;
+        pixels[4 + 2] = 3;
+        pixels[3 * 4 + 3] = 1;
+
+        let cells = CLIBackend::half_block_cells(&pixels, 4, None);
+        assert_eq!(characters(&cells), "▀▄█    ▄");
+
+        let palette = Theme::Amber.palette();
+        let cells = CLIBackend::half_block_cells(&pixels, 4, Some(palette));
+        assert_eq!(characters(&cells), "▀▀▀▀▀▀▀▀");
+        assert_eq!(
+            cells[1].colors,
+            Some((palette.colors[0], palette.colors[2]))
+        );
+        assert_eq!(
+            cells[2].colors,
+            Some((palette.colors[1], palette.colors[3]))
+        );
+    }
+
+    #[test]
+    fn differential_updates() {
+        let plain = |text: &str| -> Vec<Cell> {
+            return text
+                .chars()
+                .map(|character| Cell {
+                    character,
+                    colors: None,
+                })
+                .collect();
+        };
+        let colors = TerminalColors::TrueColor;
+
+        let mut buffer = String::new();
+        write_cell_updates(&mut buffer, None, &plain("abcd"), 2, colors);
+        assert_eq!(buffer, "\x1b[?25l\x1b[2J\x1b[1;1Hab\x1b[2;1Hcd");
+
+        buffer.clear();
+        write_cell_updates(&mut buffer, Some(&plain("abcd")), &plain("axyz"), 2, colors);
+        assert_eq!(buffer, "\x1b[1;2Hx\x1b[2;1Hyz");
+
+        buffer.clear();
+        write_cell_updates(&mut buffer, Some(&plain("ab")), &plain("ab"), 2, colors);
+        assert!(buffer.is_empty());
+
+        let mut colored = plain("ab");
+        colored[1].colors = Some((0xFFFFFF, 0x000000));
+        write_cell_updates(&mut buffer, Some(&plain("ab")), &colored, 2, colors);
+        assert_eq!(buffer, "\x1b[1;2H\x1b[38;2;255;255;255;48;2;0;0;0mb\x1b[0m");
+    }
+
+    #[test]
+    fn braille_cells() {
+        let mut pixels = vec![0; 4 * 4];
+        pixels[0] = 1;
+        pixels[4 + 1] = 2;
+        pixels[3 * 4] = 1;
+        pixels[3 * 4 + 3] = 3;
+
+        let palette = Theme::Lcd.palette();
+        let cells = CLIBackend::braille_cells(&pixels, 4, Some(palette));
+        assert_eq!(characters(&cells), "⡑⢀");
+        assert_eq!(
+            cells[0].colors,
+            Some((palette.colors[2], palette.colors[0]))
+        );
+        assert_eq!(
+            cells[1].colors,
+            Some((palette.colors[3], palette.colors[0]))
+        );
+
+        let cells = CLIBackend::braille_cells(&[0; 64 * 32], 64, None);
+        assert_eq!(cells.len(), 32 * 8);
+        assert_eq!(characters(&cells), "⠀".repeat(32 * 8));
+    }
+
+    #[test]
+    fn phosphor_decay() {
+        let palette = Palette {
+            colors: [0x000000, 0xFF8040, 0x00FF00, 0x0000FF],
+        };
+        let mut phosphor = PhosphorFilter::new(4);
+        let mut frame = |pixels: &[u8]| {
+            phosphor.tick(pixels, &palette);
+            return (phosphor.apply(pixels, &palette), phosphor.is_fading());
+        };
+
+        assert_eq!(frame(&[1, 2]), (vec![0xFF8040, 0x00FF00], false));
+        assert_eq!(frame(&[0, 2]), (vec![0xBF6030, 0x00FF00], true));
+        assert_eq!(frame(&[0, 0]), (vec![0x7F4020, 0x00BF00], true));
+        assert_eq!(frame(&[0, 0]), (vec![0x3F2010, 0x007F00], true));
+        // The last step to the background still needs drawing
+        assert_eq!(frame(&[0, 0]), (vec![0x000000, 0x003F00], true));
+        assert_eq!(frame(&[1, 0]), (vec![0xFF8040, 0x000000], true));
+        assert_eq!(frame(&[1, 0]), (vec![0xFF8040, 0x000000], false));
+
+        // Rendering more than once a frame does not speed the fade up
+        phosphor.tick(&[0, 0], &palette);
+        for _ in 0..3 {
+            assert_eq!(phosphor.apply(&[0, 0], &palette), vec![0xBF6030, 0x000000]);
+        }
+    }
+
+    #[test]
+    fn gui_scaling() {
+        let viewport = |mode: GUIScaleMode, width, height| mode.viewport(width, height, 64, 32);
+        let area = |x, y, width, height| Viewport {
+            x,
+            y,
+            width,
+            height,
+        };
+
+        // Non-multiple sizes are centred with the leftover as letterbox
+        assert_eq!(
+            viewport(GUIScaleMode::Integer, 650, 330),
+            area(5, 5, 640, 320)
+        );
+        assert_eq!(
+            viewport(GUIScaleMode::Integer, 640, 640),
+            area(0, 160, 640, 320)
+        );
+        assert_eq!(viewport(GUIScaleMode::Fit, 650, 330), area(0, 2, 650, 325));
+        assert_eq!(viewport(GUIScaleMode::Fit, 660, 300), area(30, 0, 600, 300));
+        // Too small for a whole multiple
+        assert_eq!(viewport(GUIScaleMode::Integer, 48, 48), area(0, 12, 48, 24));
+
+        let colors = [0xFFFFFF, 0x00FF00];
+        let mut buffer = vec![0xABCDEF; 6 * 4];
+        let mut draw = |effects| {
+            draw_scaled(&colors, 2, 1, &mut buffer, 6, area(1, 1, 4, 2), effects);
+            return buffer.clone();
+        };
+
+        let plain = draw(GUIEffects::default());
+        assert_eq!(plain[..6], [0; 6]);
+        assert_eq!(plain[6..12], [0, 0xFFFFFF, 0xFFFFFF, 0x00FF00, 0x00FF00, 0]);
+        assert_eq!(plain[6..12], plain[12..18]);
+        assert_eq!(plain[18..], [0; 6]);
+
+        let scanlines = draw(GUIEffects {
+            scanlines: true,
+            pixel_grid: false,
+        });
+        assert_eq!(scanlines[6..12], plain[6..12]);
+        assert_eq!(
+            scanlines[12..18],
+            [0, 0x7F7F7F, 0x7F7F7F, 0x007F00, 0x007F00, 0]
+        );
+
+        let grid = draw(GUIEffects {
+            scanlines: false,
+            pixel_grid: true,
+        });
+        assert_eq!(grid[6..12], [0, 0xFFFFFF, 0x7F7F7F, 0x00FF00, 0x007F00, 0]);
+        assert_eq!(grid[12..18], scanlines[12..18]);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{
+    palette::Palette,
+    screenshot::{self, ImageFormat},
+};
+use std::env;
+
+// Terminal protocols for showing bitmaps instead of characters
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GraphicsProtocol {
+    Sixel,
+    Kitty,
+}
+
+impl GraphicsProtocol {
+    // Asking the terminal would mean reading its reply from the same input
+    // the keypad uses, so the protocol is guessed from the environment
+    pub fn detect() -> Option<Self> {
+        return Self::from_environment(
+            env::var("TERM").as_deref().unwrap_or_default(),
+            env::var("TERM_PROGRAM").as_deref().unwrap_or_default(),
+            env::var_os("KITTY_WINDOW_ID").is_some(),
+        );
+    }
+
+    fn from_environment(term: &str, term_program: &str, kitty_window: bool) -> Option<Self> {
+        if kitty_window
+            || matches!(term, "xterm-kitty" | "xterm-ghostty")
+            || matches!(term_program, "ghostty" | "WezTerm")
+        {
+            return Some(GraphicsProtocol::Kitty);
+        }
+
+        if matches!(term_program, "iTerm.app" | "mintty")
+            || ["foot", "mlterm", "contour", "yaft"]
+                .iter()
+                .any(|name| term.starts_with(name))
+            || term.contains("sixel")
+        {
+            return Some(GraphicsProtocol::Sixel);
+        }
+
+        return None;
+    }
+
+    // Escape sequences drawing the framebuffer at the cursor, each pixel
+    // becoming a scale x scale square
+    pub fn encode(
+        self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        scale: usize,
+        palette: &Palette,
+    ) -> String {
+        return match self {
+            GraphicsProtocol::Sixel => sixel(pixels, width, height, scale, palette),
+            GraphicsProtocol::Kitty => kitty(pixels, width, height, scale, palette),
+        };
+    }
+
+    // Removes what encode left on the screen, for when the program exits
+    pub fn clear(self) -> String {
+        return match self {
+            GraphicsProtocol::Sixel => String::new(),
+            GraphicsProtocol::Kitty => format!("{esc}_Ga=d,q=2{esc}\\", esc = 27 as char),
+        };
+    }
+}
+
+fn sixel(pixels: &[u8], width: usize, height: usize, scale: usize, palette: &Palette) -> String {
+    let esc = 27 as char;
+    let scale = scale.max(1);
+    let (image_width, image_height) = (width * scale, height * scale);
+
+    let mut data = format!("{esc}Pq\"1;1;{image_width};{image_height}");
+    for (i, color) in palette.colors.iter().enumerate() {
+        // Colour registers take percentages
+        let [_, r, g, b] = color
+            .to_be_bytes()
+            .map(|channel| channel as usize * 100 / 255);
+        data.push_str(&format!("#{i};2;{r};{g};{b}"));
+    }
+
+    let pixel = |x: usize, y: usize| pixels[y / scale * width + x / scale];
+
+    // Each sixel character covers a column of six rows
+    for band in (0..image_height).step_by(6) {
+        let rows = band..(band + 6).min(image_height);
+
+        let mut first = true;
+        for color in 0..palette.colors.len() as u8 {
+            let sixels: Vec<u8> = (0..image_width)
+                .map(|x| {
+                    rows.clone()
+                        .filter(|&y| pixel(x, y) == color)
+                        .fold(0, |bits, y| bits | 1 << (y - band))
+                })
+                .collect();
+            if sixels.iter().all(|&bits| bits == 0) {
+                continue;
+            }
+
+            // Colours of a band are drawn over each other from its start
+            if !first {
+                data.push('$');
+            }
+            data.push_str(&format!("#{color}"));
+            first = false;
+            for run in sixels.chunk_by(|a, b| a == b) {
+                let character = (63 + run[0]) as char;
+                match run.len() {
+                    1..=3 => data.extend(std::iter::repeat_n(character, run.len())),
+                    length => data.push_str(&format!("!{length}{character}")),
+                }
+            }
+        }
+        data.push('-');
+    }
+    data.push_str(&format!("{esc}\\"));
+
+    return data;
+}
+
+fn kitty(pixels: &[u8], width: usize, height: usize, scale: usize, palette: &Palette) -> String {
+    const CHUNK_SIZE: usize = 4096;
+    let esc = 27 as char;
+
+    let png = screenshot::encode(pixels, width, height, scale, palette, ImageFormat::Png)
+        .expect("encoding to memory does not fail");
+    let payload = base64(&png);
+
+    // The same image and placement ids replace the previous frame. q=2
+    // keeps the terminal from replying into the keyboard input.
+    let mut data = String::with_capacity(payload.len() + 64);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(CHUNK_SIZE).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        let chunk = std::str::from_utf8(chunk).unwrap();
+        match i {
+            0 => data.push_str(&format!(
+                "{esc}_Ga=T,f=100,i=1,p=1,q=2,C=1,m={more};{chunk}{esc}\\"
+            )),
+            _ => data.push_str(&format!("{esc}_Gm={more};{chunk}{esc}\\")),
+        }
+    }
+
+    return data;
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(bits >> (18 - i * 6) & 0x3F) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+
+    return encoded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detection() {
+        let detect = GraphicsProtocol::from_environment;
+
+        assert_eq!(
+            detect("xterm-kitty", "", false),
+            Some(GraphicsProtocol::Kitty)
+        );
+        assert_eq!(
+            detect("xterm-256color", "WezTerm", false),
+            Some(GraphicsProtocol::Kitty)
+        );
+        assert_eq!(detect("screen", "", true), Some(GraphicsProtocol::Kitty));
+        assert_eq!(
+            detect("foot-extra", "", false),
+            Some(GraphicsProtocol::Sixel)
+        );
+        assert_eq!(
+            detect("xterm-256color", "iTerm.app", false),
+            Some(GraphicsProtocol::Sixel)
+        );
+        assert_eq!(detect("xterm-256color", "", false), None);
+    }
+
+    #[test]
+    fn sixel_bands() {
+        let palette = Palette {
+            colors: [0x000000, 0xFFFFFF, 0xFF0000, 0x0000FF],
+        };
+        // 5 x 4 pixels drawn at scale 2: two bands of 6 and 2 rows. The
+        // second row is lit and the last pixel uses colour 2.
+        let mut pixels = [0; 5 * 4];
+        pixels[5..10].fill(1);
+        pixels[19] = 2;
+
+        let data = GraphicsProtocol::Sixel.encode(&pixels, 5, 4, 2, &palette);
+        assert_eq!(
+            data,
+            concat!(
+                "\x1bPq\"1;1;10;8",
+                "#0;2;0;0;0#1;2;100;100;100#2;2;100;0;0#3;2;0;0;100",
+                // Rows 0, 1, 4 and 5 in colour 0, rows 2 and 3 in colour 1
+                "#0!10r$#1!10K-",
+                "#0!8B??$#2!8?BB-",
+                "\x1b\\"
+            )
+        );
+    }
+
+    #[test]
+    fn kitty_chunks() {
+        assert_eq!(base64(b"CHIP-8"), "Q0hJUC04");
+        assert_eq!(base64(b"CHIP8"), "Q0hJUDg=");
+        assert_eq!(base64(b"C8"), "Qzg=");
+        assert_eq!(base64(b"C"), "Qw==");
+
+        let data = GraphicsProtocol::Kitty.encode(&[1; 128 * 64], 128, 64, 8, &Palette::default());
+        assert!(data.starts_with("\x1b_Ga=T,f=100,i=1,p=1,q=2,C=1,m="));
+        assert!(data.ends_with("\x1b\\"));
+        // Every chunk but the last is marked as continued
+        let chunks = data.matches("\x1b_G").count();
+        assert_eq!(data.matches("m=1;").count(), chunks - 1);
+        assert_eq!(data.matches("m=0;").count(), 1);
+    }
+}
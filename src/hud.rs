@@ -0,0 +1,235 @@
+use crate::constant::cpu::GENERAL_PURPOSE_REGISTERS_COUNT;
+
+// Machine state shown by the debug overlay
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DebugInfo {
+    pub pc: u16,
+    pub index: u16,
+    pub registers: [u8; GENERAL_PURPOSE_REGISTERS_COUNT],
+    pub stack_depth: usize,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    // Disassembly of the instruction at PC
+    pub instruction: String,
+    pub frames_per_second: f32,
+    pub instructions_per_second: f32,
+}
+
+impl DebugInfo {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("PC {:03X}  I {:03X}", self.pc, self.index)];
+        for (row, values) in self.registers.chunks(4).enumerate() {
+            lines.push(
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| format!("V{:X} {value:02X}", row * 4 + i))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
+        lines.push(format!(
+            "SP {}  DT {:02X}  ST {:02X}",
+            self.stack_depth, self.delay_timer, self.sound_timer
+        ));
+        lines.push(format!("> {}", self.instruction));
+        lines.push(format!(
+            "FPS {:.0}  IPS {:.0}",
+            self.frames_per_second, self.instructions_per_second
+        ));
+
+        return lines;
+    }
+}
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+// Rows of a 3x5 glyph, the highest bit being the left column. Lowercase
+// letters other than x are drawn in uppercase.
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    return match character.to_ascii_uppercase() {
+        _ if character == 'x' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        _ => [0; GLYPH_HEIGHT],
+    };
+}
+
+// One pixel of the font per 320 window pixels keeps the text readable
+fn text_scale(width: usize) -> usize {
+    return (width / 320).max(1);
+}
+
+// Number of lines draw_text fits in a window
+pub fn text_rows(width: usize, height: usize) -> usize {
+    let scale = text_scale(width);
+
+    return height.saturating_sub(scale * 2) / ((GLYPH_HEIGHT + 2) * scale);
+}
+
+// Draws lines of text into the top left corner of a window sized buffer over
+// a darkened panel, leaving the rest of the buffer as it is. The highlighted
+// line is drawn inverted.
+pub fn draw_text(
+    buffer: &mut [u32],
+    width: usize,
+    height: usize,
+    lines: &[String],
+    highlighted: Option<usize>,
+) {
+    let scale = text_scale(width);
+    let advance = (GLYPH_WIDTH + 1) * scale;
+    let line_height = (GLYPH_HEIGHT + 2) * scale;
+
+    let columns = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    let panel_width = (columns * advance + scale * 3).min(width);
+    let panel_height = (lines.len() * line_height + scale * 2).min(height);
+
+    for row in buffer.chunks_exact_mut(width).take(panel_height) {
+        for pixel in &mut row[..panel_width] {
+            *pixel = (*pixel >> 2) & 0x3F3F3F;
+        }
+    }
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let mut color = 0xFFFFFF;
+        if highlighted == Some(line_index) {
+            let top = scale + line_index * line_height;
+            for row in buffer.chunks_exact_mut(width).skip(top).take(line_height) {
+                row[..panel_width].fill(0xFFFFFF);
+            }
+            color = 0x000000;
+        }
+
+        for (column, character) in line.chars().enumerate() {
+            let left = scale * 2 + column * advance;
+            let top = scale * 2 + line_index * line_height;
+
+            for (y, bits) in glyph(character).iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (0b100 >> x) == 0 {
+                        continue;
+                    }
+
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let (px, py) = (left + x * scale + dx, top + y * scale + dy);
+                            if px < width && py < height {
+                                buffer[py * width + px] = color;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip8::CHIP8, display::HeadlessBackend};
+
+    #[test]
+    fn overlay() {
+        let info = DebugInfo {
+            pc: 0x200,
+            index: 0x3A,
+            registers: [0x12; GENERAL_PURPOSE_REGISTERS_COUNT],
+            stack_depth: 2,
+            delay_timer: 0x3C,
+            sound_timer: 0,
+            instruction: "LD V0, 0x5".to_string(),
+            frames_per_second: 59.9,
+            instructions_per_second: 700.4,
+        };
+        let lines = info.lines();
+        assert_eq!(lines[0], "PC 200  I 03A");
+        assert_eq!(lines[4], "VC 12 VD 12 VE 12 VF 12");
+        assert_eq!(lines[5], "SP 2  DT 3C  ST 00");
+        assert_eq!(lines[6], "> LD V0, 0x5");
+        assert_eq!(lines[7], "FPS 60  IPS 700");
+
+        let mut buffer = vec![0x808080; 40 * 12];
+        draw_text(&mut buffer, 40, 12, &["1".to_string()], None);
+
+        // The panel is 1 glyph plus margins wide and 1 line plus margins high
+        assert_eq!(buffer[0], 0x202020);
+        assert_eq!(buffer[6], 0x202020);
+        assert_eq!(buffer[7], 0x808080);
+        assert_eq!(buffer[9 * 40], 0x808080);
+        // Top row of 1 is the middle column, the second row the left two
+        assert_eq!(
+            buffer[2 * 40 + 2..2 * 40 + 5],
+            [0x202020, 0xFFFFFF, 0x202020]
+        );
+        assert_eq!(
+            buffer[3 * 40 + 2..3 * 40 + 5],
+            [0xFFFFFF, 0xFFFFFF, 0x202020]
+        );
+    }
+
+    #[test]
+    fn machine_debug_info() {
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        // LD V3, 0x2A; LD I, 0x123; CALL 0x208; LD I, 0xABCD (long form)
+        chip8
+            .load_rom(&[
+                0x63, 0x2A, 0xA1, 0x23, 0x22, 0x08, 0x00, 0x00, 0xF0, 0x00, 0xAB, 0xCD,
+            ])
+            .unwrap();
+        chip8.run_cycles(3);
+
+        let info = chip8.debug_info();
+        assert_eq!(info.pc, 0x208);
+        assert_eq!(info.index, 0x123);
+        assert_eq!(info.registers[3], 0x2A);
+        assert_eq!(info.stack_depth, 1);
+        assert_eq!(info.instruction, "LD I, 0xABCD");
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{browser::MenuKey, display::SharedBackend};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Hotkey {
+    SaveState,
+    LoadState,
+    Screenshot,
+    // Starts a recording, or stops the one in progress
+    Record,
+    // Opens the ROM browser
+    Browse,
+    // Stops in the debugger before the next instruction
+    Break,
+    // Reported every frame while the key is held
+    Rewind,
+}
+
+pub trait InputBackend: Default {
+    fn read_keys(&mut self) -> Vec<u8>;
+    // None means no key will ever arrive and the machine should stop
+    fn wait_for_key(&mut self) -> Option<u8>;
+
+    // Emulator hotkeys pressed since the last call
+    fn hotkeys(&mut self) -> Vec<Hotkey> {
+        return Vec::new();
+    }
+
+    // Menu navigation pressed since the last call, only read while a menu
+    // is open
+    fn menu_keys(&mut self) -> Vec<MenuKey> {
+        return Vec::new();
+    }
+
+    // A line typed at the debugger prompt. None means no command will arrive
+    // and the machine runs on without the debugger.
+    fn read_command(&mut self, _prompt: &str) -> Option<String> {
+        return None;
+    }
+}
+
+impl<B: InputBackend> InputBackend for SharedBackend<B> {
+    fn read_keys(&mut self) -> Vec<u8> {
+        return self.borrow_mut().read_keys();
+    }
+
+    fn wait_for_key(&mut self) -> Option<u8> {
+        return self.borrow_mut().wait_for_key();
+    }
+
+    fn hotkeys(&mut self) -> Vec<Hotkey> {
+        return self.borrow_mut().hotkeys();
+    }
+
+    fn menu_keys(&mut self) -> Vec<MenuKey> {
+        return self.borrow_mut().menu_keys();
+    }
+
+    fn read_command(&mut self, prompt: &str) -> Option<String> {
+        return self.borrow_mut().read_command(prompt);
+    }
+}
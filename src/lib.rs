@@ -1,9 +1,21 @@
 #![allow(clippy::needless_return)]
 #![allow(clippy::upper_case_acronyms)]
 
+pub mod audio;
+pub mod browser;
 pub mod chip8;
 pub mod constant;
 pub mod cpu;
+pub mod debugger;
 pub mod display;
+pub mod graphics;
+pub mod hud;
+pub mod input;
+pub mod palette;
+pub mod quirks;
 pub mod ram;
+pub mod recording;
+pub mod rewind;
+pub mod screenshot;
+pub mod state;
 pub mod timer;
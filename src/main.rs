@@ -1,19 +1,420 @@
+#![allow(clippy::needless_return)]
+
 use chip_8::{
-    chip8::CHIP8,
-    display::{GUIBackend, WindowSize},
+    audio::{SquareWave, WavWriter},
+    browser::RecentRoms,
+    chip8::{CHIP8, ExecutionStatus},
+    constant::{
+        audio::{DEFAULT_TONE_FREQUENCY, DEFAULT_VOLUME},
+        chip8::CPU_INSTRUCTION_PER_SECOND,
+        display::{CHIP8_DISPLAY_HEIGHT, CHIP8_DISPLAY_WIDTH},
+    },
+    display::{
+        CLIBackend, CLIRenderMode, DisplayBackend, GUIBackend, GUIEffects, GUIScaleMode,
+        HeadlessBackend, PhosphorFilter, SharedBackend, WindowSize,
+    },
+    graphics::GraphicsProtocol,
+    input::InputBackend,
+    palette::{Palette, Theme},
+    quirks::Platform,
+};
+use clap::{ArgGroup, Parser, ValueEnum};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
-use std::path::Path;
 
-fn main() {
-    let rom_path = Path::new("/home/nima/Downloads/1-chip8-logo.ch8");
-    let rom_data = std::fs::read(rom_path).unwrap();
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    Cli,
+    Gui,
+    Headless,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CLIModeArg {
+    // Graphics when the terminal looks like it supports them, otherwise
+    // half blocks
+    Auto,
+    Characters,
+    HalfBlock,
+    Braille,
+    Sixel,
+    Kitty,
+}
+
+impl From<CLIModeArg> for CLIRenderMode {
+    fn from(mode: CLIModeArg) -> Self {
+        return match mode {
+            CLIModeArg::Auto => GraphicsProtocol::detect()
+                .map(CLIRenderMode::Graphics)
+                .unwrap_or(CLIRenderMode::HalfBlock),
+            CLIModeArg::Characters => CLIRenderMode::Characters,
+            CLIModeArg::HalfBlock => CLIRenderMode::HalfBlock,
+            CLIModeArg::Braille => CLIRenderMode::Braille,
+            CLIModeArg::Sixel => CLIRenderMode::Graphics(GraphicsProtocol::Sixel),
+            CLIModeArg::Kitty => CLIRenderMode::Graphics(GraphicsProtocol::Kitty),
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ScaleModeArg {
+    Integer,
+    Fit,
+}
+
+impl From<ScaleModeArg> for GUIScaleMode {
+    fn from(mode: ScaleModeArg) -> Self {
+        return match mode {
+            ScaleModeArg::Integer => GUIScaleMode::Integer,
+            ScaleModeArg::Fit => GUIScaleMode::Fit,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ThemeArg {
+    Classic,
+    Green,
+    Amber,
+    Lcd,
+    HighContrast,
+}
+
+impl From<ThemeArg> for Theme {
+    fn from(theme: ThemeArg) -> Self {
+        return match theme {
+            ThemeArg::Classic => Theme::Classic,
+            ThemeArg::Green => Theme::GreenPhosphor,
+            ThemeArg::Amber => Theme::Amber,
+            ThemeArg::Lcd => Theme::Lcd,
+            ThemeArg::HighContrast => Theme::HighContrast,
+        };
+    }
+}
+
+fn parse_color(text: &str) -> Result<u32, String> {
+    return Palette::parse_color(text).ok_or(format!("'{text}' is not an RRGGBB colour"));
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PlatformArg {
+    #[value(name = "chip-8")]
+    Chip8,
+    #[value(name = "chip-48")]
+    Chip48,
+    #[value(name = "schip-1.0")]
+    SuperChip10,
+    #[value(name = "schip-1.1")]
+    SuperChip11,
+    #[value(name = "xo-chip")]
+    XoChip,
+}
+
+impl From<PlatformArg> for Platform {
+    fn from(platform: PlatformArg) -> Self {
+        return match platform {
+            PlatformArg::Chip8 => Platform::CosmacVip,
+            PlatformArg::Chip48 => Platform::Chip48,
+            PlatformArg::SuperChip10 => Platform::SuperChip10,
+            PlatformArg::SuperChip11 => Platform::SuperChip11,
+            PlatformArg::XoChip => Platform::XoChip,
+        };
+    }
+}
+
+#[derive(Parser)]
+#[command(version, about = "CHIP-8 interpreter")]
+#[command(group(ArgGroup::new("capture").args(["screenshot", "record"]).multiple(true)))]
+struct Args {
+    /// Path of the ROM to run, or a directory to pick one from. Esc opens
+    /// the ROM browser while running.
+    rom: PathBuf,
+
+    /// Where to draw the screen and read the keypad from
+    #[arg(short, long, value_enum, default_value_t = Backend::Gui)]
+    backend: Backend,
+
+    /// How the CLI backend draws pixels
+    #[arg(long, value_enum, default_value_t = CLIModeArg::Auto)]
+    cli_mode: CLIModeArg,
+
+    /// Size of one CHIP-8 pixel in the GUI window
+    #[arg(short, long, default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..))]
+    scale: u16,
+
+    /// How the GUI scales the display when the window is resized
+    #[arg(long, value_enum, default_value_t = ScaleModeArg::Integer)]
+    scale_mode: ScaleModeArg,
+
+    /// Darken the bottom line of every pixel row in the GUI like a CRT
+    #[arg(long)]
+    scanlines: bool,
+
+    /// Draw a grid between pixels in the GUI
+    #[arg(long)]
+    pixel_grid: bool,
+
+    /// Open the GUI window without decorations, F11 toggles this
+    #[arg(long)]
+    borderless: bool,
+
+    /// Show machine state over the GUI display, F1 toggles this
+    #[arg(long)]
+    hud: bool,
+
+    /// Number of instructions executed per second
+    #[arg(long, default_value_t = CPU_INSTRUCTION_PER_SECOND as u32, value_parser = clap::value_parser!(u32).range(1..))]
+    ips: u32,
+
+    /// Platform whose quirks the ROM expects
+    #[arg(short, long, value_enum, default_value_t = PlatformArg::Chip8)]
+    platform: PlatformArg,
+
+    /// Pitch of the buzzer in Hz
+    #[arg(long, default_value_t = DEFAULT_TONE_FREQUENCY)]
+    tone: f32,
+
+    /// Loudness of the buzzer from 0 to 1
+    #[arg(long, default_value_t = DEFAULT_VOLUME)]
+    volume: f32,
+
+    /// Write the buzzer to a WAV file instead of playing it
+    #[arg(long, value_name = "PATH")]
+    wav: Option<PathBuf>,
+
+    /// Do not play the buzzer
+    #[cfg(feature = "audio")]
+    #[arg(long)]
+    mute: bool,
+
+    /// Stop in the debugger before the first instruction, F8 stops in it
+    /// later. Type help at its prompt for the commands.
+    #[arg(short, long)]
+    debug: bool,
+
+    /// Redraw the screen on every CLS and DRW instead of once per frame
+    #[arg(long)]
+    present_on_draw: bool,
+
+    /// Colour theme, the CLI backend keeps the terminal's colours without one
+    #[arg(long, value_enum)]
+    palette: Option<ThemeArg>,
+
+    /// Colour of lit pixels as RRGGBB
+    #[arg(long, value_parser = parse_color)]
+    foreground: Option<u32>,
+
+    /// Colour of unlit pixels as RRGGBB
+    #[arg(long, value_parser = parse_color)]
+    background: Option<u32>,
+
+    /// Save the screen to a .png, .pbm or .ppm file when the run ends
+    #[arg(long, value_name = "PATH")]
+    screenshot: Option<PathBuf>,
+
+    /// End the run after this many frames, executed as fast as possible
+    #[arg(long, value_name = "FRAMES", requires = "capture")]
+    screenshot_after: Option<u32>,
+
+    /// Size of one CHIP-8 pixel in screenshots, including those taken with F12
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    screenshot_scale: u16,
+
+    /// Record the run to an animated GIF, F10 starts and stops a recording
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Size of one CHIP-8 pixel in recordings
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    record_scale: u16,
+
+    /// Number of frames over which switched off pixels fade in the GUI
+    #[arg(long, value_name = "FRAMES", value_parser = clap::value_parser!(u32).range(1..))]
+    phosphor: Option<u32>,
+}
+
+impl Args {
+    // None when no colour option was given
+    fn palette(&self) -> Option<Palette> {
+        if self.palette.is_none() && self.foreground.is_none() && self.background.is_none() {
+            return None;
+        }
+
+        let mut palette = Theme::from(self.palette.unwrap_or(ThemeArg::Classic)).palette();
+        if let Some(foreground) = self.foreground {
+            palette = palette.with_foreground(foreground);
+        }
+        if let Some(background) = self.background {
+            palette = palette.with_background(background);
+        }
+
+        return Some(palette);
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if let Err(error) = std::fs::metadata(&args.rom) {
+        eprintln!("error: could not read '{}': {error}", args.rom.display());
+        return ExitCode::FAILURE;
+    }
+
+    let result = match args.backend {
+        Backend::Cli => match CLIBackend::try_new() {
+            Ok(mut backend) => {
+                backend.render_mode = CLIRenderMode::from(args.cli_mode);
+                backend.palette = args.palette();
+                run(backend, &args)
+            }
+            Err(error) => Err(format!("could not set up the terminal: {error}")),
+        },
+        Backend::Gui => {
+            let scale = args.scale as usize;
+            match GUIBackend::try_new(WindowSize {
+                width: CHIP8_DISPLAY_WIDTH * scale,
+                height: CHIP8_DISPLAY_HEIGHT * scale,
+            }) {
+                Ok(mut backend) => {
+                    backend.palette = args.palette().unwrap_or_default();
+                    backend.phosphor = args.phosphor.map(PhosphorFilter::new);
+                    backend.scale_mode = GUIScaleMode::from(args.scale_mode);
+                    backend.show_debug_info = args.hud;
+                    backend.effects = GUIEffects {
+                        scanlines: args.scanlines,
+                        pixel_grid: args.pixel_grid,
+                    };
+                    match backend.set_borderless(args.borderless) {
+                        Ok(()) => run(backend, &args),
+                        Err(error) => Err(format!("could not open the window: {error}")),
+                    }
+                }
+                Err(error) => Err(format!("could not open the window: {error}")),
+            }
+        }
+        Backend::Headless => run(HeadlessBackend::new(), &args),
+    };
+
+    if let Err(message) = result {
+        eprintln!("error: {message}");
+        return ExitCode::FAILURE;
+    }
+
+    return ExitCode::SUCCESS;
+}
+
+fn run<B: DisplayBackend + InputBackend>(backend: B, args: &Args) -> Result<(), String> {
+    let mut chip8 = CHIP8::new_custom_display_backend(backend);
+    chip8.set_instructions_per_second(args.ips as usize);
+    chip8.set_platform(Platform::from(args.platform));
+    chip8.set_screenshot_scale(args.screenshot_scale as usize);
+    chip8.set_recording_scale(args.record_scale as usize);
+    chip8.set_present_on_draw(args.present_on_draw);
+
+    match RecentRoms::default_file().map(RecentRoms::load) {
+        Some(Ok(recent_roms)) => chip8.set_recent_roms(recent_roms),
+        Some(Err(error)) => eprintln!("warning: could not read the recent ROMs: {error}"),
+        None => {}
+    }
+
+    if args.rom.is_dir() {
+        // Both run before a ROM could be picked from the browser
+        if args.screenshot_after.is_some() || args.record.is_some() {
+            return Err(format!(
+                "'{}' is a directory, --screenshot-after and --record need a ROM",
+                args.rom.display()
+            ));
+        }
+
+        chip8.set_rom_directory(args.rom.clone());
+        chip8
+            .open_browser()
+            .map_err(|error| format!("could not browse '{}': {error}", args.rom.display()))?;
+    } else {
+        let directory = args
+            .rom
+            .parent()
+            .filter(|directory| !directory.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        chip8.set_rom_directory(directory.to_path_buf());
+        chip8
+            .load_rom_file(&args.rom)
+            .map_err(|error| format!("could not load '{}': {error}", args.rom.display()))?;
+    }
+
+    let wave = SquareWave::new(args.tone, args.volume);
+    let wav = match &args.wav {
+        Some(path) => {
+            let wav = WavWriter::create(path, wave)
+                .map_err(|error| format!("could not create '{}': {error}", path.display()))?;
+            let wav = SharedBackend::new(RefCell::new(wav));
+            chip8.set_audio_backend(Box::new(wav.clone()));
+            Some(wav)
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "audio")]
+    if wav.is_none() && !args.mute {
+        match chip_8::audio::DeviceAudio::try_new(wave) {
+            Ok(audio) => chip8.set_audio_backend(Box::new(audio)),
+            Err(error) => eprintln!("warning: sound is off: {error}"),
+        }
+    }
+
+    if let Some(path) = &args.record {
+        chip8
+            .start_recording(path, args.record_scale as usize)
+            .map_err(|error| format!("could not create '{}': {error}", path.display()))?;
+    }
+
+    let status = match args.screenshot_after {
+        Some(frames) => run_frames(&mut chip8, frames, args.debug),
+        None => chip8.start(args.debug),
+    };
+
+    if let Some(path) = &args.screenshot {
+        chip8
+            .save_screenshot(path, args.screenshot_scale as usize)
+            .map_err(|error| format!("could not save '{}': {error}", path.display()))?;
+    }
+
+    chip8
+        .stop_recording()
+        .map_err(|error| format!("could not finish the recording: {error}"))?;
+
+    if let (Some(wav), Some(path)) = (wav, &args.wav) {
+        wav.borrow_mut()
+            .finish()
+            .map_err(|error| format!("could not write '{}': {error}", path.display()))?;
+    }
+
+    if let ExecutionStatus::Error(error) = status {
+        return Err(format!("'{}' crashed at {error}", args.rom.display()));
+    }
+
+    return Ok(());
+}
 
-    let mut chip8 = CHIP8::new_custom_display_backend(GUIBackend::new(WindowSize {
-        width: 1280,
-        height: 640,
-    }));
+fn run_frames<D: DisplayBackend, I: InputBackend>(
+    chip8: &mut CHIP8<D, I>,
+    frames: u32,
+    debug: bool,
+) -> ExecutionStatus {
+    chip8.set_debug(debug);
 
-    chip8.load_rom(&rom_data).unwrap();
+    for _ in 0..frames {
+        let status = chip8.run_frame();
+        if matches!(
+            status,
+            ExecutionStatus::Stopped | ExecutionStatus::Exited | ExecutionStatus::Error(_)
+        ) {
+            return status;
+        }
+    }
 
-    chip8.start(false);
+    return ExecutionStatus::Running;
 }
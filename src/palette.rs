@@ -0,0 +1,161 @@
+use std::env;
+
+// Colours as 0x00RRGGBB, indexed by the bitplanes a pixel is lit on
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Palette {
+    pub colors: [u32; 4],
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Theme {
+    #[default]
+    Classic,
+    GreenPhosphor,
+    Amber,
+    Lcd,
+    HighContrast,
+}
+
+impl Theme {
+    pub fn palette(self) -> Palette {
+        let colors = match self {
+            Theme::Classic => [0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555],
+            Theme::GreenPhosphor => [0x001100, 0x33FF66, 0x1E9940, 0x0F4D20],
+            Theme::Amber => [0x1A0F00, 0xFFB000, 0xB37B00, 0x664600],
+            Theme::Lcd => [0x9BBC0F, 0x0F380F, 0x306230, 0x8BAC0F],
+            Theme::HighContrast => [0x000000, 0xFFFFFF, 0xFFFF00, 0x00FFFF],
+        };
+
+        return Palette { colors };
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        return Theme::default().palette();
+    }
+}
+
+impl From<Theme> for Palette {
+    fn from(theme: Theme) -> Self {
+        return theme.palette();
+    }
+}
+
+impl Palette {
+    pub fn color(&self, pixel: u8) -> u32 {
+        return self.colors[(pixel & 0b11) as usize];
+    }
+
+    pub fn background(&self) -> u32 {
+        return self.colors[0];
+    }
+
+    pub fn foreground(&self) -> u32 {
+        return self.colors[1];
+    }
+
+    pub fn with_background(mut self, color: u32) -> Self {
+        self.colors[0] = color;
+        return self;
+    }
+
+    pub fn with_foreground(mut self, color: u32) -> Self {
+        self.colors[1] = color;
+        return self;
+    }
+
+    // Accepts RRGGBB with an optional leading #
+    pub fn parse_color(text: &str) -> Option<u32> {
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        if hex.len() != 6 {
+            return None;
+        }
+
+        return u32::from_str_radix(hex, 16).ok();
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TerminalColors {
+    TrueColor,
+    Ansi256,
+}
+
+impl TerminalColors {
+    // Terminals that support 24-bit colour advertise it in COLORTERM
+    pub fn detect() -> Self {
+        return match env::var("COLORTERM").as_deref() {
+            Ok("truecolor" | "24bit") => TerminalColors::TrueColor,
+            _ => TerminalColors::Ansi256,
+        };
+    }
+
+    // SGR escape sequence selecting the foreground and background colours
+    pub fn escape(self, foreground: u32, background: u32) -> String {
+        let esc = 27 as char;
+
+        return match self {
+            TerminalColors::TrueColor => {
+                let [_, fr, fg, fb] = foreground.to_be_bytes();
+                let [_, br, bg, bb] = background.to_be_bytes();
+                format!("{esc}[38;2;{fr};{fg};{fb};48;2;{br};{bg};{bb}m")
+            }
+            TerminalColors::Ansi256 => format!(
+                "{esc}[38;5;{};48;5;{}m",
+                Self::ansi256(foreground),
+                Self::ansi256(background)
+            ),
+        };
+    }
+
+    // Nearest entry of the 6x6x6 colour cube or the grey ramp
+    fn ansi256(color: u32) -> u8 {
+        let [_, r, g, b] = color.to_be_bytes();
+
+        if r == g && g == b {
+            return match r {
+                0..8 => 16,
+                249.. => 231,
+                _ => 232 + ((r - 8) / 10).min(23),
+            };
+        }
+
+        let level = |value: u8| ((value as u16 * 5 + 127) / 255) as u8;
+
+        return 16 + 36 * level(r) + 6 * level(g) + level(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_colors() {
+        let palette = Theme::Amber
+            .palette()
+            .with_foreground(Palette::parse_color("#102030").unwrap())
+            .with_background(Palette::parse_color("FFFFFF").unwrap());
+
+        assert_eq!(palette.color(0), 0xFFFFFF);
+        assert_eq!(palette.color(1), 0x102030);
+        assert_eq!(palette.color(2), Theme::Amber.palette().colors[2]);
+        assert_eq!(Palette::parse_color("12345"), None);
+        assert_eq!(Palette::parse_color("GGGGGG"), None);
+    }
+
+    #[test]
+    fn terminal_escapes() {
+        assert_eq!(
+            TerminalColors::TrueColor.escape(0xFFB000, 0x000102),
+            "\x1b[38;2;255;176;0;48;2;0;1;2m"
+        );
+        assert_eq!(
+            TerminalColors::Ansi256.escape(0xFF0000, 0x000000),
+            "\x1b[38;5;196;48;5;16m"
+        );
+        assert_eq!(TerminalColors::ansi256(0xFFFFFF), 231);
+        assert_eq!(TerminalColors::ansi256(0x808080), 244);
+    }
+}
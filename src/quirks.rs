@@ -0,0 +1,138 @@
+use crate::{
+    constant::ram::{MEMORY_SIZE, XO_CHIP_MEMORY_SIZE},
+    state::{StateError, StateReader, StateWriter},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Platform {
+    #[default]
+    CosmacVip,
+    Chip48,
+    SuperChip10,
+    SuperChip11,
+    XoChip,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IndexIncrement {
+    Unchanged,
+    X,
+    XPlusOne,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quirks {
+    // 8XY6/8XYE shift VY into VX instead of shifting VX in place
+    pub shift_uses_vy: bool,
+    // How far FX55/FX65 move I after the transfer
+    pub index_increment: IndexIncrement,
+    // BNNN is BXNN and jumps to XNN + VX instead of NNN + V0
+    pub jump_uses_vx: bool,
+    // 8XY1/8XY2/8XY3 set VF to 0
+    pub vf_reset: bool,
+    // Sprites are cut at the screen edge instead of wrapping around
+    pub clip_sprites: bool,
+    // DXYN waits for the next frame before the CPU continues
+    pub display_wait: bool,
+    // DXY0 draws a 16x16 sprite instead of nothing
+    pub large_sprites: bool,
+}
+
+impl Platform {
+    pub fn memory_size(self) -> usize {
+        return match self {
+            Platform::XoChip => XO_CHIP_MEMORY_SIZE,
+            _ => MEMORY_SIZE,
+        };
+    }
+
+    pub fn quirks(self) -> Quirks {
+        return match self {
+            Platform::CosmacVip => Quirks {
+                shift_uses_vy: true,
+                index_increment: IndexIncrement::XPlusOne,
+                jump_uses_vx: false,
+                vf_reset: true,
+                clip_sprites: true,
+                display_wait: true,
+                large_sprites: false,
+            },
+            Platform::Chip48 => Quirks {
+                shift_uses_vy: false,
+                index_increment: IndexIncrement::X,
+                jump_uses_vx: true,
+                vf_reset: false,
+                clip_sprites: true,
+                display_wait: false,
+                large_sprites: false,
+            },
+            Platform::SuperChip10 => Quirks {
+                index_increment: IndexIncrement::X,
+                ..Platform::SuperChip11.quirks()
+            },
+            Platform::SuperChip11 => Quirks {
+                shift_uses_vy: false,
+                index_increment: IndexIncrement::Unchanged,
+                jump_uses_vx: true,
+                vf_reset: false,
+                clip_sprites: true,
+                display_wait: false,
+                large_sprites: true,
+            },
+            Platform::XoChip => Quirks {
+                shift_uses_vy: true,
+                index_increment: IndexIncrement::XPlusOne,
+                jump_uses_vx: false,
+                vf_reset: false,
+                clip_sprites: false,
+                display_wait: false,
+                large_sprites: true,
+            },
+        };
+    }
+}
+
+impl Quirks {
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.shift_uses_vy);
+        writer.write_u8(match self.index_increment {
+            IndexIncrement::Unchanged => 0,
+            IndexIncrement::X => 1,
+            IndexIncrement::XPlusOne => 2,
+        });
+        writer.write_bool(self.jump_uses_vx);
+        writer.write_bool(self.vf_reset);
+        writer.write_bool(self.clip_sprites);
+        writer.write_bool(self.display_wait);
+        writer.write_bool(self.large_sprites);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, StateError> {
+        return Ok(Quirks {
+            shift_uses_vy: reader.read_bool()?,
+            index_increment: match reader.read_u8()? {
+                0 => IndexIncrement::Unchanged,
+                1 => IndexIncrement::X,
+                2 => IndexIncrement::XPlusOne,
+                _ => return Err(StateError::InvalidData("index increment quirk")),
+            },
+            jump_uses_vx: reader.read_bool()?,
+            vf_reset: reader.read_bool()?,
+            clip_sprites: reader.read_bool()?,
+            display_wait: reader.read_bool()?,
+            large_sprites: reader.read_bool()?,
+        });
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        return Platform::default().quirks();
+    }
+}
+
+impl From<Platform> for Quirks {
+    fn from(platform: Platform) -> Self {
+        return platform.quirks();
+    }
+}
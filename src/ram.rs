@@ -1,4 +1,11 @@
-use crate::constant::ram::{FONT_LOCATION, MEMORY_SIZE, ROM_START_LOCATION};
+use crate::{
+    constant::ram::{
+        BIG_FONT_LOCATION, FONT_LOCATION, MEMORY_SIZE, ROM_START_LOCATION, XO_CHIP_MEMORY_SIZE,
+    },
+    state::{StateError, StateReader, StateWriter},
+};
+use core::fmt;
+use std::io;
 
 // ToDo: Load this from a file
 const FONT_SET: [u8; 80] = [
@@ -20,12 +27,53 @@ const FONT_SET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+const BIG_FONT_SET: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
 #[derive(Debug)]
 pub enum RomError {
     InvalidRomSize(usize),
+    Io(io::Error),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::InvalidRomSize(size) => write!(
+                f,
+                "ROM is {size} bytes, which does not fit in memory after 0x{ROM_START_LOCATION:X}"
+            ),
+            RomError::Io(error) => write!(f, "{error}"),
+        }
+    }
 }
+
+impl std::error::Error for RomError {}
+
+impl From<io::Error> for RomError {
+    fn from(error: io::Error) -> Self {
+        return RomError::Io(error);
+    }
+}
+
 pub struct Ram {
-    pub memory: [u8; MEMORY_SIZE],
+    pub memory: Vec<u8>,
 }
 
 impl Default for Ram {
@@ -36,14 +84,24 @@ impl Default for Ram {
 
 impl Ram {
     pub fn new() -> Self {
-        let mut memory: [u8; MEMORY_SIZE] = [0; MEMORY_SIZE];
+        return Self::with_size(MEMORY_SIZE);
+    }
+
+    pub fn with_size(size: usize) -> Self {
+        let mut memory = vec![0; size];
         memory[FONT_LOCATION..=0x9F].copy_from_slice(&FONT_SET);
+        memory[BIG_FONT_LOCATION..BIG_FONT_LOCATION + BIG_FONT_SET.len()]
+            .copy_from_slice(&BIG_FONT_SET);
 
         return Ram { memory };
     }
 
+    pub fn resize(&mut self, size: usize) {
+        self.memory.resize(size, 0);
+    }
+
     pub fn load_rom(&mut self, rom_data: &[u8]) -> Result<(), RomError> {
-        if self.memory.len() - rom_data.len() < ROM_START_LOCATION {
+        if rom_data.len() > self.memory.len() - ROM_START_LOCATION {
             return Err(RomError::InvalidRomSize(rom_data.len()));
         }
 
@@ -52,4 +110,19 @@ impl Ram {
 
         return Ok(());
     }
+
+    pub(crate) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.memory);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, StateError> {
+        let memory = reader.read_bytes()?;
+        if memory.len() != MEMORY_SIZE && memory.len() != XO_CHIP_MEMORY_SIZE {
+            return Err(StateError::InvalidData("memory size"));
+        }
+
+        return Ok(Ram {
+            memory: memory.to_vec(),
+        });
+    }
 }
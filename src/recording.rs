@@ -0,0 +1,266 @@
+use crate::{constant::chip8::FRAMES_PER_SECOND, palette::Palette};
+use core::fmt;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Gif(gif::EncodingError),
+    Io(io::Error),
+    // GIF sizes are 16 bit
+    TooLarge { width: usize, height: usize },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Gif(error) => write!(f, "{error}"),
+            RecordingError::Io(error) => write!(f, "{error}"),
+            RecordingError::TooLarge { width, height } => write!(
+                f,
+                "{width}x{height} is larger than a GIF can be ({max}x{max})",
+                max = u16::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<io::Error> for RecordingError {
+    fn from(error: io::Error) -> Self {
+        return RecordingError::Io(error);
+    }
+}
+
+impl From<gif::EncodingError> for RecordingError {
+    fn from(error: gif::EncodingError) -> Self {
+        return RecordingError::Gif(error);
+    }
+}
+
+// Writes one captured frame per 60 Hz tick to an animated GIF. Runs of
+// identical frames become a single GIF frame with a longer delay.
+pub struct GifRecorder<W: Write> {
+    encoder: Option<gif::Encoder<W>>,
+    width: u16,
+    height: u16,
+    // Palette indices of the frame waiting for its delay to be known
+    pending: Option<Vec<u8>>,
+    pending_since: u64,
+    ticks: u64,
+    frames: usize,
+}
+
+impl GifRecorder<BufWriter<File>> {
+    pub fn create(
+        path: &Path,
+        width: usize,
+        height: usize,
+        palette: &Palette,
+    ) -> Result<Self, RecordingError> {
+        // Checked before the file is created
+        gif_size(width, height)?;
+
+        return Self::new(BufWriter::new(File::create(path)?), width, height, palette);
+    }
+}
+
+fn gif_size(width: usize, height: usize) -> Result<(u16, u16), RecordingError> {
+    return match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(RecordingError::TooLarge { width, height }),
+    };
+}
+
+impl<W: Write> GifRecorder<W> {
+    // Every captured frame is stretched to width x height
+    pub fn new(
+        writer: W,
+        width: usize,
+        height: usize,
+        palette: &Palette,
+    ) -> Result<Self, RecordingError> {
+        let (width, height) = gif_size(width, height)?;
+        let colors: Vec<u8> = palette
+            .colors
+            .iter()
+            .flat_map(|color| color.to_be_bytes()[1..].to_vec())
+            .collect();
+
+        let mut encoder = gif::Encoder::new(writer, width, height, &colors)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        return Ok(GifRecorder {
+            encoder: Some(encoder),
+            width,
+            height,
+            pending: None,
+            pending_since: 0,
+            ticks: 0,
+            frames: 0,
+        });
+    }
+
+    // Number of 60 Hz ticks captured so far
+    pub fn ticks(&self) -> u64 {
+        return self.ticks;
+    }
+
+    // Number of GIF frames written so far
+    pub fn frames(&self) -> usize {
+        return self.frames;
+    }
+
+    pub fn capture(
+        &mut self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Result<(), RecordingError> {
+        // Nearest neighbour, so both scaling up and resolution switches
+        // during the recording keep whole pixels
+        let (frame_width, frame_height) = (self.width as usize, self.height as usize);
+        let mut frame = Vec::with_capacity(frame_width * frame_height);
+        for y in 0..frame_height {
+            let row = &pixels[y * height / frame_height * width..][..width];
+            frame.extend((0..frame_width).map(|x| row[x * width / frame_width]));
+        }
+
+        if self.pending.as_ref() != Some(&frame) {
+            self.write_pending()?;
+            self.pending = Some(frame);
+            self.pending_since = self.ticks;
+        }
+        self.ticks += 1;
+
+        return Ok(());
+    }
+
+    fn write_pending(&mut self) -> Result<(), RecordingError> {
+        let (Some(pixels), Some(encoder)) = (self.pending.take(), &mut self.encoder) else {
+            return Ok(());
+        };
+
+        // GIF delays are in hundredths of a second, so the rounding error
+        // is spread over the frames instead of adding up
+        let centiseconds = |ticks: u64| (ticks * 100 + 30) / FRAMES_PER_SECOND as u64;
+        let delay = centiseconds(self.ticks) - centiseconds(self.pending_since);
+
+        let frame = gif::Frame {
+            width: self.width,
+            height: self.height,
+            delay: delay.min(u16::MAX as u64) as u16,
+            buffer: pixels.into(),
+            ..gif::Frame::default()
+        };
+        encoder.write_frame(&frame)?;
+        self.frames += 1;
+
+        return Ok(());
+    }
+
+    pub fn finish(mut self) -> Result<W, RecordingError> {
+        self.write_pending()?;
+        let encoder = self.encoder.take().expect("encoder is only taken here");
+
+        return Ok(encoder.into_inner()?);
+    }
+}
+
+impl<W: Write> Drop for GifRecorder<W> {
+    fn drop(&mut self) {
+        let _ = self.write_pending();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip8::CHIP8, display::HeadlessBackend, quirks::Platform};
+
+    #[test]
+    fn gif_frames() {
+        let palette = Palette {
+            colors: [0x000000, 0xFFFFFF, 0x102030, 0x405060],
+        };
+        let mut recorder = GifRecorder::new(Vec::new(), 4, 2, &palette).unwrap();
+
+        let blank = [0; 2];
+        let lit = [1, 2];
+        for pixels in [&blank, &blank, &lit, &blank, &blank, &blank] {
+            recorder.capture(pixels, 2, 1).unwrap();
+        }
+        assert_eq!(recorder.ticks(), 6);
+        let data = recorder.finish().unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(data.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (4, 2));
+        assert_eq!(
+            decoder.global_palette().unwrap(),
+            &[0, 0, 0, 255, 255, 255, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60]
+        );
+
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((frame.delay, frame.buffer.to_vec()));
+        }
+
+        // 2, 1 and 3 ticks at 60 Hz add up to exactly 10 hundredths
+        assert_eq!(
+            frames,
+            vec![
+                (3, vec![0; 8]),
+                (2, vec![1, 1, 2, 2, 1, 1, 2, 2]),
+                (5, vec![0; 8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn machine_recording() {
+        let path = std::env::temp_dir().join(format!("chip8-recording-{}.gif", std::process::id()));
+
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        chip8.set_platform(Platform::Chip48);
+        // LD V0, 0x05; LD F, V0; DRW V0, V0, 5; JP 0x206
+        chip8
+            .load_rom(&[0x60, 0x05, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x06])
+            .unwrap();
+
+        // 64 x 32 at scale 1024 no longer fits in a GIF's 16 bit size
+        assert!(matches!(
+            chip8.start_recording(&path, 1024),
+            Err(RecordingError::TooLarge {
+                width: 65536,
+                height: 32768
+            })
+        ));
+        assert!(!chip8.is_recording() && !path.exists());
+
+        chip8.start_recording(&path, 2).unwrap();
+        assert!(chip8.is_recording());
+        for _ in 0..3 {
+            chip8.run_frame();
+        }
+        assert_eq!(chip8.stop_recording().unwrap(), Some(3));
+        assert!(!chip8.is_recording());
+        assert_eq!(chip8.stop_recording().unwrap(), None);
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(data.as_slice())
+            .unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (128, 64));
+        // The sprite was drawn in the first frame and never changed
+        assert_eq!(decoder.read_next_frame().unwrap().unwrap().delay, 5);
+        assert!(decoder.read_next_frame().unwrap().is_none());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::constant::rewind::{REWIND_FRAMES, REWIND_MEMORY_LIMIT};
+use std::collections::VecDeque;
+
+// Turns a snapshot into the one taken a frame before it
+enum Delta {
+    Full(Vec<u8>),
+    // Runs of [unchanged length u32][changed length u32][changed bytes XOR newer]
+    Xor(Vec<u8>),
+}
+
+impl Delta {
+    fn new(newer: &[u8], older: &[u8]) -> Self {
+        if newer.len() != older.len() {
+            return Delta::Full(older.to_vec());
+        }
+
+        let mut encoded = Vec::new();
+        let mut position = 0;
+        while position < older.len() {
+            let unchanged = newer[position..]
+                .iter()
+                .zip(&older[position..])
+                .take_while(|(new, old)| new == old)
+                .count();
+            position += unchanged;
+
+            let changed = newer[position..]
+                .iter()
+                .zip(&older[position..])
+                .take_while(|(new, old)| new != old)
+                .count();
+
+            encoded.extend_from_slice(&(unchanged as u32).to_le_bytes());
+            encoded.extend_from_slice(&(changed as u32).to_le_bytes());
+            encoded.extend(
+                newer[position..position + changed]
+                    .iter()
+                    .zip(&older[position..position + changed])
+                    .map(|(new, old)| new ^ old),
+            );
+            position += changed;
+        }
+
+        return Delta::Xor(encoded);
+    }
+
+    fn apply(&self, newer: &[u8]) -> Vec<u8> {
+        let encoded = match self {
+            Delta::Full(older) => return older.clone(),
+            Delta::Xor(encoded) => encoded,
+        };
+
+        let mut older = newer.to_vec();
+        let mut position = 0;
+        let mut runs = encoded.as_slice();
+        while !runs.is_empty() {
+            let unchanged = u32::from_le_bytes(runs[0..4].try_into().unwrap()) as usize;
+            let changed = u32::from_le_bytes(runs[4..8].try_into().unwrap()) as usize;
+            position += unchanged;
+
+            for (byte, difference) in older[position..position + changed]
+                .iter_mut()
+                .zip(&runs[8..8 + changed])
+            {
+                *byte ^= difference;
+            }
+            position += changed;
+            runs = &runs[8 + changed..];
+        }
+
+        return older;
+    }
+
+    fn size(&self) -> usize {
+        return match self {
+            Delta::Full(data) | Delta::Xor(data) => data.len(),
+        };
+    }
+}
+
+// Ring buffer of per-frame snapshots. Only the newest one is kept whole,
+// every older one is stored as the difference to the frame after it.
+pub struct RewindBuffer {
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Delta>,
+    capacity: usize,
+    memory_limit: usize,
+    size: usize,
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RewindBuffer {
+    pub fn new() -> Self {
+        return Self::with_limits(REWIND_FRAMES, REWIND_MEMORY_LIMIT);
+    }
+
+    pub fn with_limits(capacity: usize, memory_limit: usize) -> Self {
+        return RewindBuffer {
+            latest: None,
+            deltas: VecDeque::new(),
+            capacity,
+            memory_limit,
+            size: 0,
+        };
+    }
+
+    pub fn set_limits(&mut self, capacity: usize, memory_limit: usize) {
+        self.capacity = capacity;
+        self.memory_limit = memory_limit;
+        self.trim();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.capacity > 0;
+    }
+
+    // Number of frames that can be rewound
+    pub fn len(&self) -> usize {
+        return self.deltas.len() + self.latest.is_some() as usize;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.latest.is_none();
+    }
+
+    // Bytes used by the stored snapshots
+    pub fn size(&self) -> usize {
+        return self.size;
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.size = 0;
+    }
+
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if let Some(latest) = self.latest.take() {
+            let delta = Delta::new(&snapshot, &latest);
+            self.size += delta.size();
+            self.size -= latest.len();
+            self.deltas.push_back(delta);
+        }
+        self.size += snapshot.len();
+        self.latest = Some(snapshot);
+
+        self.trim();
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let latest = self.latest.take()?;
+        self.size -= latest.len();
+
+        if let Some(delta) = self.deltas.pop_back() {
+            let older = delta.apply(&latest);
+            self.size -= delta.size();
+            self.size += older.len();
+            self.latest = Some(older);
+        }
+
+        return Some(latest);
+    }
+
+    // Drops the newest frames and returns the oldest of them
+    pub fn rewind(&mut self, frames: usize) -> Option<Vec<u8>> {
+        let mut snapshot = None;
+        for _ in 0..frames {
+            match self.pop() {
+                Some(older) => snapshot = Some(older),
+                None => break,
+            }
+        }
+
+        return snapshot;
+    }
+
+    fn trim(&mut self) {
+        if self.capacity == 0 {
+            self.clear();
+            return;
+        }
+
+        while self.len() > self.capacity
+            || (self.size > self.memory_limit && !self.deltas.is_empty())
+        {
+            let delta = self.deltas.pop_front().unwrap();
+            self.size -= delta.size();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip8::CHIP8, display::HeadlessBackend};
+
+    #[test]
+    fn rewinds_in_order() {
+        let mut buffer = RewindBuffer::with_limits(3, usize::MAX);
+        let snapshots: Vec<Vec<u8>> = vec![
+            vec![0, 0, 0, 0],
+            vec![0, 1, 0, 0],
+            vec![0, 1, 2, 3],
+            vec![9, 9],
+            vec![9, 8],
+        ];
+        for snapshot in snapshots.iter() {
+            buffer.push(snapshot.clone());
+        }
+
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.pop().unwrap(), snapshots[4]);
+        assert_eq!(buffer.rewind(2).unwrap(), snapshots[2]);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.size(), 0);
+        assert_eq!(buffer.rewind(1), None);
+    }
+
+    #[test]
+    fn memory_limit() {
+        let mut buffer = RewindBuffer::with_limits(1000, 8192);
+        let mut snapshot = vec![0; 4096];
+        for frame in 0..100 {
+            snapshot[frame * 40] = 1;
+            buffer.push(snapshot.clone());
+        }
+
+        // One byte changes per frame, so older frames cost a few bytes each
+        assert_eq!(buffer.len(), 100);
+        assert!(buffer.size() < 8192);
+
+        buffer.set_limits(1000, 4096);
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.pop().unwrap(), snapshot);
+
+        buffer.set_limits(0, 4096);
+        buffer.push(snapshot);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn machine_rewind() {
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        // ADD V0, 1; JP 0x200
+        chip8.load_rom(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        chip8.set_instructions_per_frame(2);
+
+        let mut states = Vec::new();
+        for _ in 0..10 {
+            states.push(chip8.save_state());
+            chip8.run_frame();
+        }
+
+        assert_eq!(chip8.rewind_frames(), 10);
+        assert_eq!(chip8.rewind(3), 3);
+        assert_eq!(chip8.save_state(), states[7]);
+        assert_eq!(chip8.rewind(100), 7);
+        assert_eq!(chip8.save_state(), states[0]);
+        assert_eq!(chip8.rewind(1), 0);
+    }
+}
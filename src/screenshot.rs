@@ -0,0 +1,179 @@
+use crate::palette::Palette;
+use core::fmt;
+use std::{io, path::Path};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImageFormat {
+    Png,
+    // Black and white, every lit pixel is black whatever the palette
+    Pbm,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        return match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "pbm" => Some(ImageFormat::Pbm),
+            "ppm" => Some(ImageFormat::Ppm),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    UnsupportedFormat(String),
+    Png(png::EncodingError),
+    Io(io::Error),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::UnsupportedFormat(path) => {
+                write!(f, "'{path}' does not end in .png, .pbm or .ppm")
+            }
+            ScreenshotError::Png(error) => write!(f, "{error}"),
+            ScreenshotError::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+impl From<io::Error> for ScreenshotError {
+    fn from(error: io::Error) -> Self {
+        return ScreenshotError::Io(error);
+    }
+}
+
+impl From<png::EncodingError> for ScreenshotError {
+    fn from(error: png::EncodingError) -> Self {
+        return ScreenshotError::Png(error);
+    }
+}
+
+// Encodes a framebuffer, each pixel becoming a scale x scale square
+pub fn encode(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    scale: usize,
+    palette: &Palette,
+    format: ImageFormat,
+) -> Result<Vec<u8>, ScreenshotError> {
+    let scale = scale.max(1);
+    let (image_width, image_height) = (width * scale, height * scale);
+
+    // Scaled rows of pixel values
+    let rows = pixels.chunks_exact(width).flat_map(|row| {
+        let row: Vec<u8> = row
+            .iter()
+            .flat_map(|&pixel| std::iter::repeat_n(pixel, scale))
+            .collect();
+        std::iter::repeat_n(row, scale)
+    });
+
+    let mut data = Vec::new();
+    match format {
+        ImageFormat::Pbm => {
+            data.extend_from_slice(format!("P4\n{image_width} {image_height}\n").as_bytes());
+            for row in rows {
+                for byte in row.chunks(8) {
+                    let bits = byte.iter().enumerate().fold(0u8, |bits, (i, &pixel)| {
+                        bits | ((pixel != 0) as u8) << (7 - i)
+                    });
+                    data.push(bits);
+                }
+            }
+        }
+        ImageFormat::Ppm => {
+            data.extend_from_slice(format!("P6\n{image_width} {image_height}\n255\n").as_bytes());
+            for row in rows {
+                for pixel in row {
+                    data.extend_from_slice(&palette.color(pixel).to_be_bytes()[1..]);
+                }
+            }
+        }
+        ImageFormat::Png => {
+            let rgb: Vec<u8> = rows
+                .flatten()
+                .flat_map(|pixel| {
+                    let [_, r, g, b] = palette.color(pixel).to_be_bytes();
+                    [r, g, b]
+                })
+                .collect();
+
+            let mut encoder = png::Encoder::new(&mut data, image_width as u32, image_height as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&rgb)?;
+            writer.finish()?;
+        }
+    }
+
+    return Ok(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn netpbm() {
+        let palette = Palette {
+            colors: [0x000000, 0xFFFFFF, 0x102030, 0x405060],
+        };
+        let pixels = [1, 0, 0, 2];
+
+        let pbm = encode(&pixels, 2, 2, 1, &palette, ImageFormat::Pbm).unwrap();
+        assert_eq!(pbm, b"P4\n2 2\n\x80\x40");
+
+        let ppm = encode(&pixels, 2, 2, 2, &palette, ImageFormat::Ppm).unwrap();
+        let header = b"P6\n4 4\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 4 * 4 * 3);
+        // Second image row still belongs to the first pixel row
+        let row = |y: usize| &ppm[header.len() + y * 12..header.len() + (y + 1) * 12];
+        assert_eq!(row(1), &[255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            row(3),
+            &[0, 0, 0, 0, 0, 0, 0x10, 0x20, 0x30, 0x10, 0x20, 0x30]
+        );
+    }
+
+    #[test]
+    fn png() {
+        let png = encode(
+            &[3; 64 * 32],
+            64,
+            32,
+            3,
+            &Palette::default(),
+            ImageFormat::Png,
+        )
+        .unwrap();
+
+        let decoder = png::Decoder::new(io::Cursor::new(png));
+        let mut reader = decoder.read_info().unwrap();
+        let mut rgb = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut rgb).unwrap();
+
+        assert_eq!((info.width, info.height), (64 * 3, 32 * 3));
+        assert_eq!(&rgb[..3], &Palette::default().color(3).to_be_bytes()[1..]);
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("shot.PNG")),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(ImageFormat::from_path(Path::new("shot.bmp")), None);
+        assert_eq!(ImageFormat::from_path(Path::new("shot")), None);
+    }
+}
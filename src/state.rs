@@ -0,0 +1,243 @@
+use crate::constant::state::{STATE_MAGIC, STATE_VERSION};
+use core::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum StateError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    TrailingData(usize),
+    InvalidData(&'static str),
+    Io(io::Error),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidMagic => write!(f, "not a CHIP-8 save state"),
+            StateError::UnsupportedVersion(version) => write!(
+                f,
+                "save state has format version {version} but version {STATE_VERSION} is expected"
+            ),
+            StateError::Truncated => write!(f, "save state ends unexpectedly"),
+            StateError::TrailingData(length) => {
+                write!(f, "save state has {length} unexpected bytes at the end")
+            }
+            StateError::InvalidData(field) => write!(f, "save state has an invalid {field}"),
+            StateError::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<io::Error> for StateError {
+    fn from(error: io::Error) -> Self {
+        return StateError::Io(error);
+    }
+}
+
+pub struct StateWriter {
+    buffer: Vec<u8>,
+}
+
+impl Default for StateWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        let mut writer = StateWriter { buffer: Vec::new() };
+        writer.buffer.extend_from_slice(&STATE_MAGIC);
+        writer.write_u16(STATE_VERSION);
+
+        return writer;
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        return self.buffer;
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        let mut reader = StateReader { data };
+
+        if reader.take(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(StateError::InvalidMagic);
+        }
+
+        let version = reader.read_u16()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        return Ok(reader);
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < length {
+            return Err(StateError::Truncated);
+        }
+
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+
+        return Ok(taken);
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StateError> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, StateError> {
+        return match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::InvalidData("flag")),
+        };
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StateError> {
+        return Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()));
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, StateError> {
+        return Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()));
+    }
+
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], StateError> {
+        let length = self.read_u32()? as usize;
+
+        return self.take(length);
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        return self
+            .read_bytes()?
+            .try_into()
+            .map_err(|_| StateError::InvalidData("array length"));
+    }
+
+    // Checks that the whole state was read
+    pub fn finish(self) -> Result<(), StateError> {
+        if !self.data.is_empty() {
+            return Err(StateError::TrailingData(self.data.len()));
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip8::CHIP8, display::HeadlessBackend, quirks::Platform};
+
+    #[test]
+    fn round_trip() {
+        let mut writer = StateWriter::new();
+        writer.write_u8(0x12);
+        writer.write_bool(true);
+        writer.write_u16(0x3456);
+        writer.write_u32(0x789ABCDE);
+        writer.write_bytes(&[1, 2, 3]);
+        let data = writer.finish();
+
+        let mut reader = StateReader::new(&data).unwrap();
+        assert_eq!(reader.read_u8().unwrap(), 0x12);
+        assert!(reader.read_bool().unwrap());
+        assert_eq!(reader.read_u16().unwrap(), 0x3456);
+        assert_eq!(reader.read_u32().unwrap(), 0x789ABCDE);
+        assert_eq!(reader.read_array::<3>().unwrap(), [1, 2, 3]);
+        assert!(matches!(reader.read_u8(), Err(StateError::Truncated)));
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut data = StateWriter::new().finish();
+        data[STATE_MAGIC.len()] = data[STATE_MAGIC.len()].wrapping_add(1);
+
+        assert!(matches!(
+            StateReader::new(&data),
+            Err(StateError::UnsupportedVersion(version)) if version == STATE_VERSION + 1
+        ));
+
+        assert!(matches!(
+            StateReader::new(b"PNG\0\x01\x00"),
+            Err(StateError::InvalidMagic)
+        ));
+    }
+
+    #[test]
+    fn machine_round_trip() {
+        let mut chip8 = CHIP8::new_custom_display_backend(HeadlessBackend::new());
+        chip8.set_platform(Platform::SuperChip11);
+        // LD V0, 0x05; LD F, V0; DRW V0, V0, 5; LD DT, V0
+        chip8
+            .load_rom(&[0x60, 0x05, 0xF0, 0x29, 0xD0, 0x05, 0xF0, 0x15])
+            .unwrap();
+        chip8.run_cycles(4);
+        let saved = chip8.save_state();
+
+        chip8.run_cycles(1);
+        chip8.set_platform(Platform::CosmacVip);
+        assert_ne!(chip8.save_state(), saved);
+
+        chip8.load_state(&saved).unwrap();
+        assert_eq!(chip8.save_state(), saved);
+        assert_eq!(chip8.quirks(), Platform::SuperChip11.quirks());
+
+        let mut truncated = saved.clone();
+        truncated.pop();
+        let mut extended = saved.clone();
+        extended.push(0);
+        let mut other_version = saved.clone();
+        other_version[STATE_MAGIC.len()] = other_version[STATE_MAGIC.len()].wrapping_add(1);
+
+        chip8.run_cycles(1);
+        let before = chip8.save_state();
+        assert!(matches!(
+            chip8.load_state(&truncated),
+            Err(StateError::Truncated)
+        ));
+        assert!(matches!(
+            chip8.load_state(&extended),
+            Err(StateError::TrailingData(1))
+        ));
+        assert!(matches!(
+            chip8.load_state(&other_version),
+            Err(StateError::UnsupportedVersion(_))
+        ));
+        assert_eq!(chip8.save_state(), before);
+    }
+}
@@ -1,9 +1,5 @@
-use std::time::{Duration, Instant};
-
 pub struct Timer {
     value: u8,
-    frequency: u8,
-    start: Instant,
 }
 
 impl Default for Timer {
@@ -14,41 +10,33 @@ impl Default for Timer {
 
 impl Timer {
     pub fn new() -> Self {
-        return Timer {
-            value: 0,
-            frequency: 60,
-            start: Instant::now(),
-        };
+        return Timer { value: 0 };
     }
 
     pub fn set_value(&mut self, value: u8) {
         self.value = value;
-
-        self.start = Instant::now();
     }
 
-    pub fn get_value(&mut self) -> u8 {
-        let ticks = self.start.elapsed().as_micros() as u64 / (1000000 / self.frequency as u64);
-        self.start += Duration::from_micros(1000000 * ticks);
-
-        if ticks > u8::MAX as u64 {
-            // ToDo: cold_path()
-            self.value = 0;
-        } else {
-            let (value, overflow) = self.value.overflowing_sub(ticks as u8);
-            self.value = value * !overflow as u8;
-        }
-
+    pub fn get_value(&self) -> u8 {
         return self.value;
     }
+
+    // Called once per emulated 60 Hz frame
+    pub fn tick(&mut self) {
+        self.value = self.value.saturating_sub(1);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::thread::sleep;
-
     use super::*;
 
+    fn wait_ticks(timer: &mut Timer, ticks: usize) {
+        for _ in 0..ticks {
+            timer.tick();
+        }
+    }
+
     #[test]
     fn wait_zero_ticks() {
         let mut timer: Timer = Timer::new();
@@ -62,7 +50,7 @@ mod tests {
         let mut timer = Timer::new();
         timer.set_value(u8::MAX);
 
-        sleep(Duration::from_millis(1000 / timer.frequency as u64 + 1));
+        wait_ticks(&mut timer, 1);
 
         assert_eq!(timer.get_value(), u8::MAX - 1);
     }
@@ -72,7 +60,7 @@ mod tests {
         let mut timer = Timer::new();
         timer.set_value(u8::MAX);
 
-        sleep(Duration::from_millis(100000 / timer.frequency as u64 + 1));
+        wait_ticks(&mut timer, 100);
 
         assert_eq!(timer.get_value(), u8::MAX - 100);
     }
@@ -82,7 +70,7 @@ mod tests {
         let mut timer = Timer::new();
         timer.set_value(u8::MAX);
 
-        sleep(Duration::from_millis(255000 / timer.frequency as u64 + 1));
+        wait_ticks(&mut timer, 255);
 
         assert_eq!(timer.get_value(), 0);
     }
@@ -92,8 +80,20 @@ mod tests {
         let mut timer = Timer::new();
         timer.set_value(u8::MAX);
 
-        sleep(Duration::from_millis(256000 / timer.frequency as u64 + 1));
+        wait_ticks(&mut timer, 256);
 
         assert_eq!(timer.get_value(), 0);
     }
+
+    #[test]
+    fn set_value_restarts_countdown() {
+        let mut timer = Timer::new();
+        timer.set_value(10);
+
+        wait_ticks(&mut timer, 4);
+        timer.set_value(10);
+        wait_ticks(&mut timer, 4);
+
+        assert_eq!(timer.get_value(), 6);
+    }
 }